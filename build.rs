@@ -0,0 +1,12 @@
+use std::error::Error;
+
+/// Protobuf definitions for the gRPC service
+const PROTOS: &[&str] = &["gsd/v1/gsd.proto"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Compile protos without requiring a protoc install.
+    let file_descriptors = protox::compile(PROTOS, ["proto"])?;
+    tonic_build::configure().compile_fds(file_descriptors)?;
+    println!("cargo:rerun-if-changed=proto");
+    Ok(())
+}
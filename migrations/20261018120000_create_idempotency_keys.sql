@@ -0,0 +1,13 @@
+create table idempotency_keys (
+    route varchar(255) not null,
+    key varchar(255) not null,
+    request_hash bytea not null,
+    status smallint,
+    content_type varchar(255),
+    response bytea,
+    created_at timestamptz not null default now(),
+    expires_at timestamptz not null,
+    primary key (route, key)
+);
+
+create index idempotency_keys_expires_at_index on idempotency_keys using btree(expires_at);
@@ -0,0 +1,23 @@
+-- Names and owners are trimmed and NFC normalized, without control characters, and owners are
+-- lowercased so they match regardless of case. Bring existing rows in line, then enforce it.
+update stories
+set name = normalize(btrim(regexp_replace(name, '[[:cntrl:]]', ' ', 'g')), NFC),
+    owner = lower(normalize(btrim(regexp_replace(owner, '[[:cntrl:]]', ' ', 'g')), NFC));
+
+update tasks
+set name = normalize(btrim(regexp_replace(name, '[[:cntrl:]]', ' ', 'g')), NFC);
+
+-- Whitespace-only names were accepted before
+update stories set name = 'untitled' where name = '';
+update tasks set name = 'untitled' where name = '';
+update stories set owner = 'backlog' where owner = '';
+
+alter table stories
+    add constraint stories_name_check
+        check (char_length(name) between 1 and 100 and name = btrim(name)),
+    add constraint stories_owner_check
+        check (char_length(owner) between 1 and 100 and owner = lower(owner));
+
+alter table tasks
+    add constraint tasks_name_check
+        check (char_length(name) between 1 and 100 and name = btrim(name));
@@ -0,0 +1,9 @@
+create table task_recurrences (
+    task_id uuid primary key references tasks(id) on delete cascade,
+    rule varchar(255) not null,
+    next_at timestamptz not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create index task_recurrences_next_at_index on task_recurrences using btree(next_at);
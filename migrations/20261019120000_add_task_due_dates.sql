@@ -0,0 +1,7 @@
+-- Tasks may have a due date. Owners are reminded once as it approaches, and again if it changes.
+alter table tasks
+    add column due_at timestamptz,
+    add column reminded_at timestamptz;
+
+create index tasks_due_at_index on tasks using btree(due_at)
+    where due_at is not null and deleted_at is null and status = 'incomplete';
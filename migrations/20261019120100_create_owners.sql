@@ -0,0 +1,8 @@
+-- Settings for owners, who otherwise only exist as a column on stories
+create table owners (
+    owner varchar(100) primary key,
+    timezone varchar(64) not null default 'UTC',
+    email varchar(255),
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
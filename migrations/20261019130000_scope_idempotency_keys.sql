@@ -0,0 +1,7 @@
+-- Scope idempotency keys to the client that sent them, so clients can't collide with or replay
+-- each other's requests. Stored keys have no client, and are only kept for a day, so drop them.
+delete from idempotency_keys;
+
+alter table idempotency_keys add column client text not null;
+alter table idempotency_keys drop constraint idempotency_keys_pkey;
+alter table idempotency_keys add primary key (client, route, key);
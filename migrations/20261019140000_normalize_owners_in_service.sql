@@ -0,0 +1,25 @@
+-- Trim names of all Unicode white space, as the service does, rather than just spaces.
+update stories
+set name = regexp_replace(
+    name,
+    '^[\u0009-\u000d \u0085\u00a0\u1680\u2000-\u200a\u2028\u2029\u202f\u205f\u3000]+|[\u0009-\u000d \u0085\u00a0\u1680\u2000-\u200a\u2028\u2029\u202f\u205f\u3000]+$',
+    '',
+    'g'
+);
+
+update tasks
+set name = regexp_replace(
+    name,
+    '^[\u0009-\u000d \u0085\u00a0\u1680\u2000-\u200a\u2028\u2029\u202f\u205f\u3000]+|[\u0009-\u000d \u0085\u00a0\u1680\u2000-\u200a\u2028\u2029\u202f\u205f\u3000]+$',
+    '',
+    'g'
+);
+
+update stories set name = 'untitled' where name = '';
+update tasks set name = 'untitled' where name = '';
+
+-- Owners are case folded by the service, which lower() doesn't match, so existing owners are
+-- normalized by `gsd migrate up` after migrations run and the database only checks lengths.
+alter table stories drop constraint stories_owner_check;
+alter table stories
+    add constraint stories_owner_check check (char_length(owner) between 1 and 100);
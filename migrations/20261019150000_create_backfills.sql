@@ -0,0 +1,5 @@
+-- Data backfills run by the service after migrations, recorded so each runs once.
+create table backfills (
+    name varchar(100) primary key,
+    completed_at timestamptz not null default now()
+);
@@ -0,0 +1,4 @@
+-- Reminders are leased while they are sent, and only marked sent once delivered, so a scheduler
+-- that stops mid-batch leaves them to be claimed again when the lease expires.
+alter table tasks
+    add column reminder_claimed_at timestamptz;
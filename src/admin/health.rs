@@ -0,0 +1,203 @@
+use crate::{api::ApiCtx, repo::MigrationState, Result};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Instant};
+
+/// Readiness checks that are reported without failing readiness
+const OPTIONAL_CHECKS: [&str; 1] = ["replica"];
+
+/// The outcome of a single readiness check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+}
+
+/// A readiness check result with its latency.
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// The response body for health endpoints.
+#[derive(Debug, Serialize)]
+pub struct Health {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<&'static str, Check>,
+}
+
+/// Health routes
+pub fn routes() -> Router<Arc<ApiCtx>> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+/// Liveness: the process is up and serving requests.
+async fn healthz() -> Json<Health> {
+    Json(Health {
+        status: CheckStatus::Pass,
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Readiness: the database is reachable, migrated, and the service is not shutting down. The
+/// replica is checked too, but reads fall back to the primary without it.
+async fn readyz(State(ctx): State<Arc<ApiCtx>>) -> (StatusCode, Json<Health>) {
+    let mut checks = BTreeMap::new();
+
+    let draining = ctx.is_draining();
+    checks.insert(
+        "shutdown",
+        Check {
+            status: if draining {
+                CheckStatus::Fail
+            } else {
+                CheckStatus::Pass
+            },
+            latency_ms: 0.0,
+            message: draining.then(|| "draining connections".into()),
+        },
+    );
+
+    let database = check(async {
+        ctx.admin_repo.ping().await?;
+        Ok(None)
+    });
+    checks.insert("database", database.await);
+
+    if ctx.config.db_replica_host.is_some() {
+        let replica = check(async {
+            ctx.admin_repo.ping_replica().await.transpose()?;
+            Ok(None)
+        });
+        checks.insert("replica", replica.await);
+    }
+
+    let migrations = check(async {
+        let status = ctx.admin_repo.migration_status().await?;
+        let pending = status
+            .iter()
+            .filter(|m| m.state == MigrationState::Pending)
+            .count();
+        let mismatched = status
+            .iter()
+            .filter(|m| m.state == MigrationState::ChecksumMismatch)
+            .count();
+        if pending + mismatched == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(format!(
+                "{} pending, {} checksum mismatch",
+                pending, mismatched
+            )))
+        }
+    });
+    checks.insert("migrations", migrations.await);
+
+    // Reads fall back to the primary while the replica is down, so it's reported but not required
+    let ready = checks
+        .iter()
+        .filter(|(name, _)| !OPTIONAL_CHECKS.contains(name))
+        .all(|(_, c)| c.status == CheckStatus::Pass);
+    let (status, code) = if ready {
+        (CheckStatus::Pass, StatusCode::OK)
+    } else {
+        (CheckStatus::Fail, StatusCode::SERVICE_UNAVAILABLE)
+    };
+
+    (code, Json(Health { status, checks }))
+}
+
+/// Time a check, which passes unless it errors or returns a failure message.
+async fn check<F>(f: F) -> Check
+where
+    F: Future<Output = Result<Option<String>>>,
+{
+    let start = Instant::now();
+    let result = f.await;
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    match result {
+        Ok(None) => Check {
+            status: CheckStatus::Pass,
+            latency_ms,
+            message: None,
+        },
+        Ok(Some(message)) => Check {
+            status: CheckStatus::Fail,
+            latency_ms,
+            message: Some(message),
+        },
+        Err(err) => Check {
+            status: CheckStatus::Fail,
+            latency_ms,
+            message: Some(err.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::tests::api_ctx,
+        repo::{self, Replica},
+    };
+    use axum::{body::Body, extract::Request};
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[tokio::test]
+    async fn liveness_without_database() {
+        let router = routes().with_state(api_ctx());
+        let request = Request::get("/healthz").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn not_ready_without_database_or_while_draining() {
+        let ctx = api_ctx();
+        ctx.start_draining();
+
+        let (code, Json(health)) = readyz(State(ctx)).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, CheckStatus::Fail);
+        for name in ["database", "migrations", "shutdown"] {
+            assert_eq!(health.checks[name].status, CheckStatus::Fail, "{}", name);
+        }
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn ready_while_replica_is_down() {
+        // Set up postgres test container backed API context, with a replica that never answers
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = repo::tests::setup_pg_pool(&container).await;
+        let replica = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let replica = Arc::new(Replica::new(replica));
+        let mut config = (*api_ctx().config).clone();
+        config.db_replica_host = Some("127.0.0.1".into());
+        let ctx = Arc::new(ApiCtx::new(Arc::new(config), pool, Some(replica)));
+
+        let (code, Json(health)) = readyz(State(ctx)).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(health.checks["replica"].status, CheckStatus::Fail);
+        assert_eq!(health.checks["database"].status, CheckStatus::Pass);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::api::ApiCtx;
+use axum::Router;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::sync::Arc;
+
+// Liveness and readiness checks
+mod health;
+
+// Prometheus metrics
+mod prometheus;
+
+pub use health::routes as health_routes;
+pub use prometheus::{install_recorder, track_requests};
+
+/// Operational endpoints, served on a separate listener from the API.
+pub struct Admin {
+    ctx: Arc<ApiCtx>,
+    metrics: PrometheusHandle,
+}
+
+impl Admin {
+    /// Create a new service
+    pub fn new(ctx: Arc<ApiCtx>, metrics: PrometheusHandle) -> Self {
+        Self { ctx, metrics }
+    }
+
+    /// Define admin routes, mapping paths to handlers.
+    pub fn routes(self) -> Router {
+        health::routes()
+            .merge(prometheus::routes(self.metrics))
+            .with_state(self.ctx)
+    }
+}
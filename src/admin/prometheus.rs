@@ -0,0 +1,132 @@
+use crate::api::ApiCtx;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Extension, Router,
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{sync::Arc, time::Instant};
+
+/// Request latency histogram
+const REQUEST_DURATION: &str = "gsd_http_request_duration_seconds";
+
+/// Latency buckets, from 5ms to 10s
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Install the global prometheus recorder, returning a handle for rendering metrics.
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.into()), DURATION_BUCKETS)?
+        .install_recorder()
+}
+
+/// Middleware that counts requests and records latency per route and status code.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    // Label with the route pattern rather than the raw path to keep cardinality bounded.
+    let path = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str().to_owned(),
+        None => "unmatched".to_owned(),
+    };
+    let method = request.method().to_string();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let latency = start.elapsed().as_secs_f64();
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("gsd_http_requests_total", &labels).increment(1);
+    metrics::histogram!(REQUEST_DURATION, &labels).record(latency);
+
+    response
+}
+
+/// Metrics routes
+pub fn routes(handle: PrometheusHandle) -> Router<Arc<ApiCtx>> {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .layer(Extension(handle))
+}
+
+/// Render metrics in the prometheus text format
+async fn get_metrics(
+    State(ctx): State<Arc<ApiCtx>>,
+    Extension(handle): Extension<PrometheusHandle>,
+) -> String {
+    tracing::debug!("get_metrics");
+    record_gauges(&ctx).await;
+    handle.render()
+}
+
+/// Sample pool and domain gauges at scrape time.
+async fn record_gauges(ctx: &ApiCtx) {
+    let pool = ctx.admin_repo.pool_stats();
+    metrics::gauge!("gsd_db_pool_connections", "state" => "size").set(pool.size as f64);
+    metrics::gauge!("gsd_db_pool_connections", "state" => "idle").set(pool.idle as f64);
+    metrics::gauge!("gsd_db_pool_connections", "state" => "in_use")
+        .set(pool.size.saturating_sub(pool.idle as u32) as f64);
+
+    match ctx.admin_repo.ping().await {
+        Ok(latency) => metrics::gauge!("gsd_db_ping_seconds").set(latency.as_secs_f64()),
+        Err(err) => tracing::warn!("metrics: database ping failed: {}", err),
+    }
+
+    match ctx.admin_repo.open_tasks().await {
+        Ok(count) => metrics::gauge!("gsd_open_tasks").set(count as f64),
+        Err(err) => tracing::warn!("metrics: counting open tasks failed: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use axum::{body::Body, http::StatusCode, middleware};
+    use tower::ServiceExt;
+
+    #[test]
+    fn requests_and_errors_are_counted() {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.into()), DURATION_BUCKETS)
+            .unwrap()
+            .build_recorder();
+        let handle = recorder.handle();
+
+        let router = Router::new()
+            .route(
+                "/stories/:id",
+                get(|| async {
+                    Err::<(), _>(Error::NotFound {
+                        message: "story not found".into(),
+                    })
+                }),
+            )
+            .layer(middleware::from_fn(track_requests));
+
+        // Use a current thread runtime so the thread local recorder sees every metric.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let status = metrics::with_local_recorder(&recorder, || {
+            runtime.block_on(async {
+                let request = Request::get("/stories/1").body(Body::empty()).unwrap();
+                router.oneshot(request).await.unwrap().status()
+            })
+        });
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let rendered = handle.render();
+        assert!(rendered.contains(
+            r#"gsd_http_requests_total{method="GET",path="/stories/:id",status="404"} 1"#
+        ));
+        assert!(rendered.contains(r#"gsd_http_request_duration_seconds_bucket{"#));
+        assert!(rendered.contains(r#"gsd_errors_total{kind="not_found"} 1"#));
+    }
+}
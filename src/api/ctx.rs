@@ -1,25 +1,112 @@
 use crate::{
+    api::RateLimiters,
     config::Config,
-    repo::{StoryRepo, TaskRepo},
+    repo::{
+        AdminRepo, ExportRepo, IdempotencyRepo, OwnerRepo, RecurrenceRepo, ReminderRepo, Replica,
+        StoryRepo, TaskRepo,
+    },
 };
 use sqlx::postgres::PgPool;
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
 // NOTE: Add drivers here
 
 #[derive(Clone)]
 pub struct ApiCtx {
     pub config: Arc<Config>,
+    pub admin_repo: Arc<AdminRepo>,
+    pub export_repo: Arc<ExportRepo>,
+    pub idempotency_repo: Arc<IdempotencyRepo>,
+    pub owner_repo: Arc<OwnerRepo>,
+    pub recurrence_repo: Arc<RecurrenceRepo>,
+    pub reminder_repo: Arc<ReminderRepo>,
     pub story_repo: Arc<StoryRepo>,
     pub task_repo: Arc<TaskRepo>,
+    pub rate_limiters: Arc<RateLimiters>,
+    draining: Arc<AtomicBool>,
+    shutdown: CancellationToken,
+    workers: TaskTracker,
 }
 
 impl ApiCtx {
-    pub fn new(config: Arc<Config>, db: Arc<PgPool>) -> Self {
+    pub fn new(config: Arc<Config>, db: Arc<PgPool>, replica: Option<Arc<Replica>>) -> Self {
+        let rate_limiters = RateLimiters::new(config.rate_limit_reads, config.rate_limit_writes);
         Self {
             config,
-            story_repo: Arc::new(StoryRepo::new(Arc::clone(&db))),
-            task_repo: Arc::new(TaskRepo::new(Arc::clone(&db))),
+            admin_repo: Arc::new(AdminRepo::with_replica(Arc::clone(&db), replica.clone())),
+            export_repo: Arc::new(ExportRepo::new(Arc::clone(&db))),
+            idempotency_repo: Arc::new(IdempotencyRepo::new(Arc::clone(&db))),
+            owner_repo: Arc::new(OwnerRepo::new(Arc::clone(&db))),
+            recurrence_repo: Arc::new(RecurrenceRepo::new(Arc::clone(&db))),
+            reminder_repo: Arc::new(ReminderRepo::new(Arc::clone(&db))),
+            story_repo: Arc::new(StoryRepo::with_replica(Arc::clone(&db), replica.clone())),
+            task_repo: Arc::new(TaskRepo::with_replica(Arc::clone(&db), replica)),
+            rate_limiters: Arc::new(rate_limiters),
+            draining: Arc::new(AtomicBool::new(false)),
+            shutdown: CancellationToken::new(),
+            workers: TaskTracker::new(),
         }
     }
+
+    /// Mark the service as shutting down, so readiness checks fail.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the service is shutting down.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// A token that is cancelled once connections should stop being accepted.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Stop accepting connections and signal background workers to stop.
+    pub fn shutdown(&self) {
+        self.start_draining();
+        self.shutdown.cancel();
+    }
+
+    /// Spawn a background worker, which should return once the shutdown token is cancelled.
+    pub fn spawn_worker<F, Fut>(&self, worker: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.workers.spawn(worker(self.shutdown_token()));
+    }
+
+    /// Wait for background workers to finish after shutdown.
+    pub async fn wait_for_workers(&self) {
+        self.workers.close();
+        self.workers.wait().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::tests::api_ctx;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn shutdown_stops_workers() {
+        let ctx = api_ctx();
+        ctx.spawn_worker(|shutdown| async move { shutdown.cancelled().await });
+        assert!(!ctx.is_draining());
+
+        ctx.shutdown();
+        assert!(ctx.is_draining());
+        tokio::time::timeout(Duration::from_secs(1), ctx.wait_for_workers())
+            .await
+            .expect("worker did not stop");
+    }
 }
@@ -1,68 +1,182 @@
-use crate::domain::{Status, Story, Task};
-use serde::Deserialize;
+use crate::{
+    api::parse::Syntax,
+    domain::{OwnerSettings, Status, Story, Task},
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
 use std::{fmt::Debug, str::FromStr};
+use unicode_normalization::UnicodeNormalization;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
 
-// Min string length bytes
-const MIN_LEN: u64 = 1;
+// Min name and owner length in characters, after normalizing
+pub const MIN_LEN: u64 = 1;
 
-// Max string length bytes
-const MAX_LEN: u64 = 100;
+// Max name and owner length in characters, after normalizing. Matches `varchar(100)` columns,
+// which also count characters.
+pub const MAX_LEN: u64 = 100;
+
+/// Normalize user input before validating it, so limits apply to the values that are stored.
+pub trait Normalize {
+    fn normalize(self) -> Self;
+}
 
 // The query parameters for getting stories
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetStoriesParams {
     pub owner: Option<String>,
 }
 
+// The query parameters for exporting an owner's stories
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    pub include_deleted: Option<bool>,
+}
+
+// The query parameters for importing an owner's stories
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportParams {
+    pub preserve_ids: Option<bool>,
+}
+
+// The query parameters for listing an owner's incomplete tasks
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OwnerTasksParams {
+    /// Only tasks due within this long from now, e.g. `2d` or `1h 30m`
+    #[param(value_type = Option<String>, example = "2d")]
+    pub due_within: Option<String>,
+}
+
+// The query parameters for importing a story from a plain text list
+#[derive(Debug, Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportStoryParams {
+    pub owner: Option<String>,
+    pub name: Option<String>,
+    pub format: Option<Syntax>,
+}
+
 /// The POST body for creating stories
-#[derive(Debug, Deserialize, Default, Validate)]
+#[derive(Debug, Deserialize, Default, Validate, ToSchema)]
 pub struct CreateStoryBody {
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(function = "validate_text", message = "control characters not allowed"))]
+    #[schema(min_length = 1, max_length = 100)]
     pub name: String,
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(
+        function = "validate_optional_text",
+        message = "control characters not allowed"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
     pub owner: Option<String>,
 }
 
+impl Normalize for CreateStoryBody {
+    fn normalize(self) -> Self {
+        Self {
+            name: normalize_name(&self.name),
+            owner: self.owner.as_deref().map(normalize_owner),
+        }
+    }
+}
+
 /// The POST body for creating tasks
-#[derive(Debug, Deserialize, Default, Validate)]
+#[derive(Debug, Deserialize, Default, Validate, ToSchema)]
 pub struct CreateTaskBody {
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(function = "validate_text", message = "control characters not allowed"))]
+    #[schema(min_length = 1, max_length = 100)]
     pub name: String,
     pub story_id: Uuid,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl Normalize for CreateTaskBody {
+    fn normalize(self) -> Self {
+        Self {
+            name: normalize_name(&self.name),
+            ..self
+        }
+    }
 }
 
 /// The PATCH body for updating tasks
-#[derive(Debug, Deserialize, Default, Validate)]
+#[derive(Debug, Deserialize, Default, Validate, ToSchema)]
 pub struct PatchTaskBody {
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(
+        function = "validate_optional_text",
+        message = "control characters not allowed"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
     pub name: Option<String>,
     #[validate(custom(function = "validate_status", message = "unmatched enum variant"))]
+    #[schema(value_type = Option<Status>)]
     pub status: Option<String>,
+    /// Set to `null` to remove the due date
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<DateTime<Utc>>, nullable)]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+}
+
+impl Normalize for PatchTaskBody {
+    fn normalize(self) -> Self {
+        Self {
+            name: self.name.as_deref().map(normalize_name),
+            ..self
+        }
+    }
 }
 
 impl PatchTaskBody {
     /// Helper to unwrap fields to update for a task, falling back to existing values.
-    pub fn unwrap(self, task: Task) -> (String, Status) {
+    pub fn unwrap(self, task: Task) -> (String, Status, Option<DateTime<Utc>>) {
         let name = self.name.unwrap_or(task.name);
         let status = match self.status {
             Some(s) => Status::from_str(&s).unwrap_or(task.status),
             None => task.status,
         };
-        (name, status)
+        let due_at = self.due_at.unwrap_or(task.due_at);
+        (name, status, due_at)
     }
 }
 
 /// The PATCH body for updating stories
-#[derive(Debug, Deserialize, Default, Validate)]
+#[derive(Debug, Deserialize, Default, Validate, ToSchema)]
 pub struct PatchStoryBody {
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(
+        function = "validate_optional_text",
+        message = "control characters not allowed"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
     pub name: Option<String>,
     #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(
+        function = "validate_optional_text",
+        message = "control characters not allowed"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
     pub owner: Option<String>,
 }
 
+impl Normalize for PatchStoryBody {
+    fn normalize(self) -> Self {
+        Self {
+            name: self.name.as_deref().map(normalize_name),
+            owner: self.owner.as_deref().map(normalize_owner),
+        }
+    }
+}
+
 impl PatchStoryBody {
     /// Helper to unwrap fields to update for a story, falling back to existing values.
     pub fn unwrap(self, story: Story) -> (String, String) {
@@ -72,6 +186,77 @@ impl PatchStoryBody {
     }
 }
 
+/// An owner given in a path, validated like the owner of a new story
+#[derive(Debug, Validate)]
+pub struct OwnerPath {
+    #[validate(length(min = "MIN_LEN", max = "MAX_LEN", message = "invalid length"))]
+    #[validate(custom(function = "validate_text", message = "control characters not allowed"))]
+    pub owner: String,
+}
+
+impl Normalize for OwnerPath {
+    fn normalize(self) -> Self {
+        Self {
+            owner: normalize_owner(&self.owner),
+        }
+    }
+}
+
+/// The PUT body for owner settings
+#[derive(Debug, Deserialize, Default, Validate, ToSchema)]
+pub struct OwnerSettingsBody {
+    /// IANA time zone, defaults to `UTC`
+    #[validate(custom(function = "validate_timezone", message = "unknown time zone"))]
+    #[schema(example = "Europe/Dublin")]
+    pub timezone: Option<String>,
+    #[validate(email(message = "invalid email"))]
+    #[validate(length(max = 255, message = "invalid length"))]
+    #[schema(max_length = 255)]
+    pub email: Option<String>,
+}
+
+impl Normalize for OwnerSettingsBody {
+    fn normalize(self) -> Self {
+        Self {
+            timezone: self.timezone.map(|tz| tz.trim().to_owned()),
+            email: self.email.map(|email| email.trim().to_owned()),
+        }
+    }
+}
+
+impl OwnerSettingsBody {
+    /// Helper to unwrap settings for an owner, with defaults for missing fields.
+    pub fn unwrap(self, owner: String) -> OwnerSettings {
+        let defaults = OwnerSettings::new(owner);
+        OwnerSettings {
+            timezone: self
+                .timezone
+                .and_then(|tz| tz.parse().ok())
+                .unwrap_or(defaults.timezone),
+            email: self.email,
+            ..defaults
+        }
+    }
+}
+
+/// Deserialize a field that is present as `Some`, even when `null`, so a missing field and
+/// `null` can mean different things.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Custom time zone validation function
+fn validate_timezone(timezone_opt: &Option<String>) -> Result<(), ValidationError> {
+    match timezone_opt.as_deref().map(str::parse::<Tz>) {
+        Some(Err(_)) => Err(ValidationError::new("invalid_timezone")),
+        _ => Ok(()),
+    }
+}
+
 /// Custom status validation function
 fn validate_status(status_opt: &Option<String>) -> Result<(), ValidationError> {
     match status_opt {
@@ -82,3 +267,118 @@ fn validate_status(status_opt: &Option<String>) -> Result<(), ValidationError> {
         },
     }
 }
+
+/// Trim surrounding whitespace and NFC normalize a name, so visually identical names are stored
+/// the same way.
+pub fn normalize_name(name: &str) -> String {
+    name.trim().nfc().collect()
+}
+
+/// Normalize an owner like a name, and case fold it so owners match regardless of case.
+pub fn normalize_owner(owner: &str) -> String {
+    normalize_name(&caseless::default_case_fold_str(owner))
+}
+
+/// Reject text containing control characters, such as line breaks and tabs.
+fn validate_text(text: &str) -> Result<(), ValidationError> {
+    if text.chars().any(char::is_control) {
+        Err(ValidationError::new("control_characters"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Reject optional text containing control characters.
+fn validate_optional_text(text: &Option<String>) -> Result<(), ValidationError> {
+    text.as_deref().map_or(Ok(()), validate_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_names_and_owners() {
+        // "e" followed by a combining acute accent composes to a single character
+        assert_eq!(normalize_name("  Cafe\u{301} Racer\t"), "Caf\u{e9} Racer");
+        assert_eq!(normalize_owner(" Alice "), "alice");
+        assert_eq!(normalize_owner("ÉLISE"), "\u{e9}lise");
+        // Owners are case folded, not just lowercased, and trimmed of any white space
+        assert_eq!(normalize_owner("Straße"), normalize_owner("STRASSE"));
+        assert_eq!(normalize_owner("ΟΔΟΣ"), normalize_owner("οδος"));
+        assert_eq!(normalize_owner("\u{a0}alice\u{3000}"), "alice");
+    }
+
+    #[test]
+    fn validate_normalized_names() {
+        let body = |name: &str| CreateStoryBody {
+            name: name.into(),
+            owner: None,
+        };
+        assert!(body("Suttree").normalize().validate().is_ok());
+        assert!(body("   ").normalize().validate().is_err());
+        assert!(body("Outer\nDark").normalize().validate().is_err());
+        // Limits count characters, not bytes
+        assert!(body(&"é".repeat(MAX_LEN as usize))
+            .normalize()
+            .validate()
+            .is_ok());
+        let decomposed = "e\u{301}".repeat(MAX_LEN as usize);
+        assert!(body(&decomposed).validate().is_err());
+        assert!(body(&decomposed).normalize().validate().is_ok());
+        assert!(body(&"é".repeat(MAX_LEN as usize + 1)).validate().is_err());
+    }
+
+    #[test]
+    fn patch_or_remove_due_dates() {
+        let task = Task {
+            id: Uuid::nil(),
+            story_id: Uuid::nil(),
+            name: "Suttree".into(),
+            status: Status::Incomplete,
+            due_at: Some("2026-10-19T09:00:00Z".parse().unwrap()),
+        };
+        let patch = |json: &str| {
+            let body: PatchTaskBody = serde_json::from_str(json).unwrap();
+            body.unwrap(task.clone()).2
+        };
+        assert_eq!(patch("{}"), task.due_at);
+        assert_eq!(patch(r#"{"due_at":null}"#), None);
+        assert_eq!(
+            patch(r#"{"due_at":"2026-10-20T09:00:00+01:00"}"#),
+            Some("2026-10-20T08:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn validate_owner_settings() {
+        let body = |timezone: &str, email: &str| OwnerSettingsBody {
+            timezone: Some(timezone.into()),
+            email: Some(email.into()),
+        };
+        let valid = body(" America/New_York ", "alice@example.com").normalize();
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.unwrap("alice".into()).timezone, Tz::America__New_York);
+        assert!(body("Mars/Olympus_Mons", "alice@example.com")
+            .validate()
+            .is_err());
+        assert!(body("UTC", "alice").validate().is_err());
+    }
+
+    #[test]
+    fn limits_match_migrations() {
+        let columns = concat!(
+            include_str!("../../migrations/20240222203528_create_stories.sql"),
+            include_str!("../../migrations/20240222203532_create_tasks.sql"),
+        );
+        let name = format!("name varchar({})", MAX_LEN);
+        let owner = format!("owner varchar({})", MAX_LEN);
+        assert_eq!(
+            columns.matches(&name).count() + columns.matches(&owner).count(),
+            3
+        );
+        let checks = include_str!("../../migrations/20261018130000_normalize_names.sql");
+        let between = format!("between {} and {}", MIN_LEN, MAX_LEN);
+        assert_eq!(checks.matches(&between).count(), 3);
+    }
+}
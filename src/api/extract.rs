@@ -0,0 +1,208 @@
+use crate::Error;
+use axum::{
+    async_trait,
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// JSON body extractor and response that rejects with a project error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::from_request(request, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Path parameter extractor that rejects with a project error.
+#[derive(Debug)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) =
+            axum::extract::Path::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// Query string extractor that rejects with a project error.
+#[derive(Debug, Default)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) =
+            axum::extract::Query::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// Map JSON body rejections into project errors.
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+/// Map path parameter rejections into project errors.
+impl From<PathRejection> for Error {
+    fn from(rejection: PathRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+/// Map query string rejections into project errors.
+impl From<QueryRejection> for Error {
+    fn from(rejection: QueryRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+/// Choose the error for a rejection by the status axum would have sent.
+fn rejected(status: StatusCode, message: String) -> Error {
+    match status {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => Error::UnsupportedMediaType { message },
+        StatusCode::UNPROCESSABLE_ENTITY => Error::Unprocessable { message },
+        status if status.is_client_error() => Error::InvalidArgs {
+            messages: vec![message],
+        },
+        _ => Error::Internal { message },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ErrorFormat, error::with_error_format};
+    use axum::{
+        body::{self, Body},
+        http::header,
+        routing::{get, post},
+        Router,
+    };
+    use serde::Deserialize;
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Book {
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Params {
+        limit: Option<u32>,
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/echo", post(|Json(body): Json<Book>| async { Json(body) }))
+            .route(
+                "/items/:id",
+                get(
+                    |Path(id): Path<Uuid>, Query(params): Query<Params>| async move {
+                        format!("{} {:?}", id, params.limit)
+                    },
+                ),
+            )
+    }
+
+    /// Send a request, with errors rendered as problem details.
+    async fn send(request: Request) -> (StatusCode, Value) {
+        let response = with_error_format(ErrorFormat::Problem, router().oneshot(request))
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    fn post_json(content_type: &str, body: &'static str) -> Request {
+        Request::post("/echo")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn json_rejections() {
+        let (status, body) = send(post_json("application/json", r#"{"name":"Suttree"}"#)).await;
+        assert_eq!(
+            (status, body["name"].as_str()),
+            (StatusCode::OK, Some("Suttree"))
+        );
+
+        let (status, body) = send(post_json("application/json", "{")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_args");
+
+        let (status, body) = send(post_json("application/json", r#"{"name":1}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "unprocessable");
+
+        let (status, body) = send(post_json("text/plain", r#"{"name":"Suttree"}"#)).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "unsupported_media_type");
+    }
+
+    #[tokio::test]
+    async fn path_and_query_rejections() {
+        let id = Uuid::new_v4();
+        let uri = format!("/items/{}?limit=10", id);
+        let response = router()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::get("/items/suttree").body(Body::empty()).unwrap();
+        let (status, body) = send(request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_args");
+        assert!(body["detail"].as_str().unwrap().contains("UUID"));
+
+        let uri = format!("/items/{}?limit=many", id);
+        let (status, body) = send(Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_args");
+    }
+}
@@ -0,0 +1,133 @@
+use crate::Error;
+use axum::{
+    extract::{OriginalUri, Request},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::any::Any;
+
+/// Fallback handler for requests that match no route.
+pub async fn not_found(method: Method, OriginalUri(uri): OriginalUri) -> Error {
+    Error::NotFound {
+        message: format!("no route for {} {}", method, uri.path()),
+    }
+}
+
+/// Middleware that replaces the empty response axum sends when a route doesn't allow a method,
+/// keeping the allow header.
+pub async fn method_not_allowed(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED
+        || response.headers().contains_key(header::CONTENT_TYPE)
+    {
+        return response;
+    }
+
+    let allow = response.headers().get(header::ALLOW).cloned();
+    let mut response = Error::MethodNotAllowed {
+        method: method.to_string(),
+    }
+    .into_response();
+    if let Some(allow) = allow {
+        response.headers_mut().insert(header::ALLOW, allow);
+    }
+    response
+}
+
+/// Build the response for a handler that panicked, without leaking the panic message.
+pub fn panicked(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    tracing::error!("handler panicked: {}", message);
+    Error::Internal {
+        message: "unexpected error handling request".into(),
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{tests, Api},
+        config::ErrorFormat,
+        error::with_error_format,
+    };
+    use axum::{body::Body, middleware, routing::get, Router};
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use tower_http::catch_panic::CatchPanicLayer;
+    use uuid::Uuid;
+
+    async fn boom() -> &'static str {
+        panic!("boom")
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/stories", get(|| async { "ok" }))
+            .route("/panic", get(boom))
+            .fallback(not_found)
+            .layer(middleware::from_fn(method_not_allowed))
+            .layer(CatchPanicLayer::custom(panicked))
+    }
+
+    /// Send a request, with errors rendered as problem details.
+    async fn send(request: Request) -> (Response, Value) {
+        let response = with_error_format(ErrorFormat::Problem, router().oneshot(request))
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (Response::from_parts(parts, Body::empty()), body)
+    }
+
+    #[tokio::test]
+    async fn fallback_errors() {
+        let (response, body) = send(Request::get("/novels").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["detail"], "no route for GET /novels");
+
+        let request = Request::delete("/stories").body(Body::empty()).unwrap();
+        let (response, body) = send(request).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[header::ALLOW], "GET,HEAD");
+        assert_eq!(body["code"], "method_not_allowed");
+
+        let (response, body) = send(Request::get("/panic").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "internal");
+        assert_eq!(body["detail"], "unexpected error handling request");
+    }
+
+    #[tokio::test]
+    async fn failed_deletes_have_bodies() {
+        // Without a database, deletes fail after routing
+        let router = Api::new(tests::api_ctx()).routes();
+        for path in ["/stories/{}", "/tasks/{}", "/tasks/{}/recurrence"] {
+            let uri = path.replace("{}", &Uuid::new_v4().to_string());
+            let request = Request::delete(uri).body(Body::empty()).unwrap();
+            let response = with_error_format(ErrorFormat::Problem, router.clone().oneshot(request))
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "{}",
+                path
+            );
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["code"], "unavailable", "{}", path);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{
+    api::ApiCtx,
+    domain::{Status, Story, Task},
+    error::with_error_format,
+    Error, Result,
+};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{fmt::Write, sync::Arc};
+
+/// Header selecting the shape of error responses, `problem` or `legacy`
+pub const ERROR_FORMAT_HEADER: &str = "x-error-format";
+
+/// CSV media type
+const TEXT_CSV: &str = "text/csv; charset=utf-8";
+
+/// Markdown media type
+const TEXT_MARKDOWN: &str = "text/markdown; charset=utf-8";
+
+/// Media types that can be negotiated, for error messages
+const SUPPORTED: &str = "application/json, text/csv, text/markdown";
+
+/// Response formats negotiated from the accept header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    /// Select the most preferred supported format from the accept header, defaulting to JSON when
+    /// there is none. Fails when the client accepts none of the supported formats.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self> {
+        let accept = headers
+            .get(header::ACCEPT)
+            .map(|value| value.to_str().unwrap_or_default())
+            .unwrap_or_default();
+        if accept.trim().is_empty() {
+            return Ok(Format::Json);
+        }
+
+        let mut ranges: Vec<(String, f32)> = accept
+            .split(',')
+            .map(media_range)
+            .map(|(media_type, q)| (media_type.to_ascii_lowercase(), q))
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // Formats refused by name, which wildcards don't bring back
+        let refused: Vec<Format> = ranges
+            .iter()
+            .filter(|(_, q)| *q <= 0.0)
+            .filter_map(|(media_type, _)| match Self::candidates(media_type) {
+                [format] => Some(*format),
+                _ => None,
+            })
+            .collect();
+
+        ranges
+            .iter()
+            .filter(|(_, q)| *q > 0.0)
+            .flat_map(|(media_type, _)| Self::candidates(media_type))
+            .find(|format| !refused.contains(format))
+            .copied()
+            .ok_or_else(|| Error::NotAcceptable {
+                message: format!(
+                    "cannot respond with {}, expected one of {}",
+                    accept, SUPPORTED
+                ),
+            })
+    }
+
+    /// Formats matching a media range, most preferred first.
+    fn candidates(media_type: &str) -> &'static [Self] {
+        match media_type {
+            "application/json" | "application/*" => &[Format::Json],
+            "text/csv" => &[Format::Csv],
+            "text/markdown" => &[Format::Markdown],
+            "text/*" => &[Format::Markdown, Format::Csv],
+            "*/*" => &[Format::Json, Format::Markdown, Format::Csv],
+            _ => &[],
+        }
+    }
+}
+
+/// Middleware that renders errors in the shape asked for by the client, or the configured default.
+pub async fn error_format(
+    State(ctx): State<Arc<ApiCtx>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let format = request
+        .headers()
+        .get(ERROR_FORMAT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(ctx.config.error_format);
+    with_error_format(format, next.run(request)).await
+}
+
+/// Split a media range into its type and quality value.
+fn media_range(range: &str) -> (&str, f32) {
+    let mut parts = range.split(';').map(str::trim);
+    let media_type = parts.next().unwrap_or_default();
+    let q = parts
+        .filter_map(|param| param.strip_prefix("q="))
+        .find_map(|q| q.parse().ok())
+        .unwrap_or(1.0);
+    (media_type, q)
+}
+
+/// Mark a response as negotiated from the accept header, so shared caches keep one copy per format.
+pub fn vary_accept(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Render tasks as CSV with a header row.
+pub fn csv(tasks: &[Task]) -> Response {
+    let mut body = String::from("id,story_id,name,status\r\n");
+    for task in tasks {
+        let _ = write!(
+            body,
+            "{},{},{},{}\r\n",
+            task.id,
+            task.story_id,
+            csv_field(&task.name),
+            task.status
+        );
+    }
+    text(TEXT_CSV, body)
+}
+
+/// Render a story as a heading followed by a checklist of its tasks.
+pub fn markdown(story: &Story, tasks: &[Task]) -> Response {
+    let body = format!("# {}\n\n{}", single_line(&story.name), checklist(tasks));
+    text(TEXT_MARKDOWN, body)
+}
+
+/// Render tasks as a GitHub-style markdown checklist.
+pub fn markdown_checklist(tasks: &[Task]) -> Response {
+    text(TEXT_MARKDOWN, checklist(tasks))
+}
+
+/// Build a checklist with one item per task.
+fn checklist(tasks: &[Task]) -> String {
+    let mut body = String::new();
+    for task in tasks {
+        let mark = match task.status {
+            Status::Complete => 'x',
+            Status::Incomplete => ' ',
+        };
+        let _ = writeln!(body, "- [{}] {}", mark, single_line(&task.name));
+    }
+    body
+}
+
+/// Quote a CSV field when it contains delimiters, quotes or line breaks.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Collapse line breaks so a value can't escape its markdown line.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Create a text response with the given content type.
+fn text(content_type: &'static str, body: String) -> Response {
+    (
+        [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{tests, Api},
+        config::ErrorFormat,
+    };
+    use axum::{body::Body, http::StatusCode};
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn format_from_headers() {
+        let format = |value| Format::from_headers(&accept(value)).unwrap();
+        assert_eq!(
+            Format::from_headers(&HeaderMap::new()).unwrap(),
+            Format::Json
+        );
+        assert_eq!(format("text/csv"), Format::Csv);
+        assert_eq!(format("text/html, text/markdown"), Format::Markdown);
+        assert_eq!(format("text/csv;q=0.5, application/json"), Format::Json);
+        assert_eq!(format("text/*"), Format::Markdown);
+        assert_eq!(format("text/markdown;q=0, text/*"), Format::Csv);
+        assert_eq!(format("application/json;q=0, */*"), Format::Markdown);
+    }
+
+    #[test]
+    fn unsatisfiable_accept_is_not_acceptable() {
+        for value in ["text/html", "image/png, application/xml", "text/csv;q=0"] {
+            match Format::from_headers(&accept(value)) {
+                Err(err @ Error::NotAcceptable { .. }) => {
+                    assert_eq!(err.into_response().status(), StatusCode::NOT_ACCEPTABLE)
+                }
+                other => panic!("expected not acceptable for {}: {:?}", value, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn negotiated_routes_are_not_acceptable() {
+        // Negotiation fails before the database is needed
+        let router = Api::new(tests::api_ctx()).routes();
+        for path in ["/stories/{}", "/stories/{}/tasks"] {
+            let uri = path.replace("{}", &Uuid::new_v4().to_string());
+            let request = Request::get(uri)
+                .header(header::ACCEPT, "text/html")
+                .body(Body::empty())
+                .unwrap();
+            let response = with_error_format(ErrorFormat::Problem, router.clone().oneshot(request))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE, "{}", path);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["code"], "not_acceptable", "{}", path);
+        }
+    }
+
+    #[test]
+    fn vary_on_accept() {
+        let response = vary_accept(markdown_checklist(&[]));
+        assert_eq!(response.headers()[header::VARY], "accept");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], TEXT_MARKDOWN);
+    }
+
+    #[test]
+    fn render_checklist() {
+        let story_id = Uuid::new_v4();
+        let task = |name: &str, status| Task {
+            id: Uuid::new_v4(),
+            story_id,
+            name: name.into(),
+            status,
+            due_at: None,
+        };
+        let tasks = vec![
+            task("Suttree", Status::Complete),
+            task("Outer\nDark", Status::Incomplete),
+        ];
+        assert_eq!(checklist(&tasks), "- [x] Suttree\n- [ ] Outer Dark\n");
+    }
+
+    #[test]
+    fn quote_csv_fields() {
+        assert_eq!(csv_field("Suttree"), "Suttree");
+        assert_eq!(csv_field("Blood Meridian, or"), "\"Blood Meridian, or\"");
+        assert_eq!(csv_field("The \"Kid\""), "\"The \"\"Kid\"\"\"");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{domain::Task, repo::TaskRepo, Error};
+use async_graphql::dataloader::Loader;
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+/// Batch loads tasks for all stories resolved in a single graphql request.
+pub struct TaskLoader {
+    task_repo: Arc<TaskRepo>,
+}
+
+impl TaskLoader {
+    /// Constructor
+    pub fn new(task_repo: Arc<TaskRepo>) -> Self {
+        Self { task_repo }
+    }
+}
+
+impl Loader<Uuid> for TaskLoader {
+    type Value = Vec<Task>;
+    type Error = Arc<Error>;
+
+    async fn load(&self, story_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Task>>, Self::Error> {
+        tracing::debug!("load: {} stories", story_ids.len());
+
+        let tasks = self.task_repo.fetch_for_stories(story_ids).await?;
+
+        let mut result: HashMap<Uuid, Vec<Task>> = HashMap::new();
+        for task in tasks {
+            result.entry(task.story_id).or_default().push(task);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::{tests, StoryRepo};
+    use async_graphql::dataloader::DataLoader;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    /// Counts the batches loaded by the task loader.
+    struct CountingLoader {
+        inner: TaskLoader,
+        batches: Arc<AtomicUsize>,
+    }
+
+    impl Loader<Uuid> for CountingLoader {
+        type Value = Vec<Task>;
+        type Error = Arc<Error>;
+
+        async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Vec<Task>>, Self::Error> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            self.inner.load(keys).await
+        }
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repos
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+        let story_repo = StoryRepo::new(Arc::clone(&pool));
+        let task_repo = Arc::new(TaskRepo::new(Arc::clone(&pool)));
+
+        let mut story_ids = Vec::new();
+        for (name, tasks) in [("Books", 2), ("Films", 1), ("Empty", 0)] {
+            let story = story_repo
+                .create(name.into(), "alice".into())
+                .await
+                .unwrap();
+            for i in 0..tasks {
+                let name = format!("{} {}", name, i);
+                task_repo.create(story.id, name, None).await.unwrap();
+            }
+            story_ids.push(story.id);
+        }
+
+        // Set up loader under test
+        let batches = Arc::new(AtomicUsize::new(0));
+        let loader = DataLoader::new(
+            CountingLoader {
+                inner: TaskLoader::new(task_repo),
+                batches: Arc::clone(&batches),
+            },
+            tokio::spawn,
+        );
+
+        // Concurrent loads are batched, with tasks grouped by story
+        let (books, films, empty) = tokio::join!(
+            loader.load_one(story_ids[0]),
+            loader.load_one(story_ids[1]),
+            loader.load_one(story_ids[2]),
+        );
+        assert_eq!(batches.load(Ordering::SeqCst), 1);
+        let names: Vec<String> = books
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["Books 0", "Books 1"]);
+        assert_eq!(films.unwrap().unwrap().len(), 1);
+        assert_eq!(empty.unwrap(), None);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    api::{session, ApiCtx},
+    repo::read_primary,
+};
+use async_graphql::{
+    dataloader::DataLoader,
+    http::GraphiQLSource,
+    parser::{self, types::OperationType},
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use std::sync::{Arc, LazyLock};
+
+// Batch loading for nested fields
+mod loader;
+
+// Queries and mutations
+mod schema;
+
+use loader::TaskLoader;
+use schema::GsdSchema;
+
+/// The schema is built once and shared by all requests.
+static SCHEMA: LazyLock<GsdSchema> = LazyLock::new(schema::schema);
+
+/// API routes for graphql, with an optional GraphiQL UI
+pub fn routes(docs_ui: bool) -> Router<Arc<ApiCtx>> {
+    let method_router = if docs_ui {
+        post(graphql).get(graphiql)
+    } else {
+        post(graphql)
+    };
+    Router::new().route(session::GRAPHQL_PATH, method_router)
+}
+
+/// Execute a graphql request. Mutations are writes for read-your-writes, so they read from the
+/// primary and return a session token; queries may use the replica.
+async fn graphql(State(ctx): State<Arc<ApiCtx>>, request: GraphQLRequest) -> Response {
+    let request = request.into_inner();
+    if !is_mutation(&request) {
+        return GraphQLResponse::from(SCHEMA.execute(with_data(request, ctx)).await)
+            .into_response();
+    }
+
+    let result = read_primary(SCHEMA.execute(with_data(request, Arc::clone(&ctx)))).await;
+    let written = result.is_ok();
+    let mut response = GraphQLResponse::from(result).into_response();
+    if written {
+        session::issue_token(&ctx, response.headers_mut());
+    }
+    response
+}
+
+/// Whether a request runs a mutation. Requests that don't parse fail validation without one.
+fn is_mutation(request: &async_graphql::Request) -> bool {
+    let Ok(document) = parser::parse_query(&request.query) else {
+        return false;
+    };
+    let operation_name = request.operation_name.as_deref();
+    document.operations.iter().any(|(name, operation)| {
+        (operation_name.is_none() || name.map(|n| n.as_str()) == operation_name)
+            && operation.node.ty == OperationType::Mutation
+    })
+}
+
+/// Add the API context to a request, with a data loader scoped to the request.
+fn with_data(request: async_graphql::Request, ctx: Arc<ApiCtx>) -> async_graphql::Request {
+    let loader = DataLoader::new(TaskLoader::new(Arc::clone(&ctx.task_repo)), tokio::spawn);
+    request.data(ctx).data(loader)
+}
+
+/// Get the GraphiQL UI
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("graphql").finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::tests, repo};
+    use async_graphql::{Response, Variables};
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    /// The introspection query GraphiQL sends to load the schema
+    const INTROSPECTION: &str = r#"
+        query IntrospectionQuery {
+          __schema {
+            queryType { name }
+            mutationType { name }
+            types { ...FullType }
+            directives { name description locations args { ...InputValue } }
+          }
+        }
+        fragment FullType on __Type {
+          kind name description
+          fields(includeDeprecated: true) {
+            name description args { ...InputValue } type { ...TypeRef } isDeprecated deprecationReason
+          }
+          inputFields { ...InputValue }
+          interfaces { ...TypeRef }
+          enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason }
+          possibleTypes { ...TypeRef }
+        }
+        fragment InputValue on __InputValue { name description type { ...TypeRef } defaultValue }
+        fragment TypeRef on __Type {
+          kind name
+          ofType { kind name ofType { kind name ofType { kind name ofType { kind name
+            ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+        }
+    "#;
+
+    /// Execute a query, returning the response data and the first error's code, if any.
+    async fn execute(ctx: &Arc<ApiCtx>, query: &str, variables: Value) -> (Value, Option<String>) {
+        let request = async_graphql::Request::new(query).variables(Variables::from_json(variables));
+        let response: Response = SCHEMA.execute(with_data(request, Arc::clone(ctx))).await;
+        let code = response.errors.first().map(|err| {
+            err.extensions
+                .as_ref()
+                .and_then(|e| e.get("code"))
+                .map(|code| code.to_string().trim_matches('"').to_owned())
+                .unwrap_or_else(|| err.message.clone())
+        });
+        (response.data.into_json().unwrap(), code)
+    }
+
+    #[test]
+    fn mutations_are_writes() {
+        let request = |query: &str| async_graphql::Request::new(query);
+        assert!(!is_mutation(&request("{ stories { id } }")));
+        assert!(is_mutation(&request("mutation { deleteTask(id: 1) }")));
+        assert!(!is_mutation(&request("not graphql")));
+
+        // With several operations, the named one decides
+        let both = "query Q { stories { id } } mutation M { deleteTask(id: 1) }";
+        assert!(!is_mutation(&request(both).operation_name("Q")));
+        assert!(is_mutation(&request(both).operation_name("M")));
+    }
+
+    #[tokio::test]
+    async fn limit_depth_and_complexity() {
+        let ctx = tests::api_ctx();
+
+        let (data, code) = execute(&ctx, INTROSPECTION, Value::Null).await;
+        assert_eq!(code, None);
+        assert_eq!(data["__schema"]["queryType"]["name"], "Query");
+
+        let deep = format!(
+            "{{ __schema {{ types {{ {}name{} }} }} }}",
+            "ofType { ".repeat(20),
+            " }".repeat(20)
+        );
+        let (_, code) = execute(&ctx, &deep, Value::Null).await;
+        assert_eq!(code.as_deref(), Some("Query is nested too deep."));
+
+        let wide: String = (0..=schema::MAX_COMPLEXITY)
+            .map(|i| format!("f{}: __schema {{ description }} ", i))
+            .collect();
+        let (_, code) = execute(&ctx, &format!("{{ {} }}", wide), Value::Null).await;
+        assert_eq!(code.as_deref(), Some("Query is too complex."));
+    }
+
+    #[tokio::test]
+    async fn resolver_errors_have_codes() {
+        let ctx = tests::api_ctx();
+
+        // Validated before reaching the database
+        let query = "mutation($name: String!) { createStory(name: $name) { id } }";
+        let (_, code) = execute(&ctx, query, json!({ "name": "  " })).await;
+        assert_eq!(code.as_deref(), Some("validation_failed"));
+
+        // The test pool never connects
+        let query = "query($id: UUID!) { story(id: $id) { name } }";
+        let (data, code) = execute(&ctx, query, json!({ "id": Uuid::new_v4() })).await;
+        assert_eq!(code.as_deref(), Some("unavailable"));
+        assert_eq!(data, Value::Null);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed API context
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = repo::tests::setup_pg_pool(&container).await;
+        let config = Arc::clone(&tests::api_ctx().config);
+        let ctx = Arc::new(ApiCtx::new(config, pool, None));
+
+        // Create stories with tasks
+        let create_story =
+            "mutation($name: String!) { createStory(name: $name, owner: \"Alice\") { id owner } }";
+        let create_task =
+            "mutation($storyId: UUID!, $name: String!) { createTask(storyId: $storyId, name: $name) { id } }";
+        let mut story_ids = Vec::new();
+        for (name, tasks) in [("Books", 2), ("Films", 1), ("Empty", 0)] {
+            let (data, code) = execute(&ctx, create_story, json!({ "name": name })).await;
+            assert_eq!(code, None);
+            assert_eq!(data["createStory"]["owner"], "alice");
+            let story_id = data["createStory"]["id"].clone();
+            for i in 0..tasks {
+                let variables = json!({ "storyId": story_id, "name": format!("{} {}", name, i) });
+                let (_, code) = execute(&ctx, create_task, variables).await;
+                assert_eq!(code, None);
+            }
+            story_ids.push(story_id);
+        }
+
+        // Nested tasks for every story, loaded in one batch
+        let query = "{ stories(owner: \"alice\") { name taskCount completeCount tasks { name } } }";
+        let (data, code) = execute(&ctx, query, Value::Null).await;
+        assert_eq!(code, None);
+        let counts: Vec<(&str, u64)> = data["stories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                (
+                    s["name"].as_str().unwrap(),
+                    s["taskCount"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(counts, vec![("Books", 2), ("Films", 1), ("Empty", 0)]);
+        assert_eq!(data["stories"][0]["tasks"][1]["name"], "Books 1");
+
+        // Completing a task
+        let query = "query($id: UUID!) { story(id: $id) { tasks { id } } }";
+        let (data, _) = execute(&ctx, query, json!({ "id": story_ids[0] })).await;
+        let task_id = data["story"]["tasks"][0]["id"].clone();
+        let update = "mutation($id: UUID!) { updateTask(id: $id, status: COMPLETE) { status } }";
+        let (data, code) = execute(&ctx, update, json!({ "id": task_id })).await;
+        assert_eq!(code, None);
+        assert_eq!(data["updateTask"]["status"], "COMPLETE");
+        let query = "query($id: UUID!) { story(id: $id) { completeCount } }";
+        let (data, _) = execute(&ctx, query, json!({ "id": story_ids[0] })).await;
+        assert_eq!(data["story"]["completeCount"], 1);
+
+        // Deleted tasks and stories resolve to null
+        let delete = "mutation($id: UUID!) { deleteTask(id: $id) }";
+        let (data, _) = execute(&ctx, delete, json!({ "id": task_id })).await;
+        assert_eq!(data["deleteTask"], true);
+        let query = "query($id: UUID!) { task(id: $id) { name } }";
+        let (data, code) = execute(&ctx, query, json!({ "id": task_id })).await;
+        assert_eq!(code, None);
+        assert_eq!(data["task"], Value::Null);
+
+        let delete = "mutation($id: UUID!) { deleteStory(id: $id) }";
+        let (_, code) = execute(&ctx, delete, json!({ "id": Uuid::new_v4() })).await;
+        assert_eq!(code.as_deref(), Some("not_found"));
+    }
+}
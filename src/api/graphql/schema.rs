@@ -0,0 +1,248 @@
+use crate::{
+    api::{
+        dto::{
+            normalize_owner, CreateStoryBody, CreateTaskBody, Normalize, PatchStoryBody,
+            PatchTaskBody,
+        },
+        graphql::loader::TaskLoader,
+        story::BACKLOG,
+        ApiCtx,
+    },
+    domain::{Status, Story, Task},
+    Error,
+};
+use async_graphql::{
+    dataloader::DataLoader, ComplexObject, Context, EmptySubscription, ErrorExtensions,
+    MaybeUndefined, Object, Result, Schema,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+/// The GSD graphql schema
+pub type GsdSchema = Schema<Query, Mutation, EmptySubscription>;
+
+/// Deepest selection allowed, leaving room for the GraphiQL introspection query
+const MAX_DEPTH: usize = 16;
+
+/// Most fields a single request may select, counting each field once
+pub(super) const MAX_COMPLEXITY: usize = 500;
+
+/// Build the graphql schema, rejecting requests that nest too deep or select too many fields.
+pub fn schema() -> GsdSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Get the API context from the request data.
+fn api_ctx<'a>(ctx: &Context<'a>) -> &'a Arc<ApiCtx> {
+    ctx.data_unchecked::<Arc<ApiCtx>>()
+}
+
+/// Map not found errors to null values.
+fn optional<T>(result: crate::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotFound { .. }) => Ok(None),
+        Err(err) => Err(err.extend()),
+    }
+}
+
+/// Nested story fields, resolved in batches
+#[ComplexObject]
+impl Story {
+    /// Tasks for the story
+    async fn tasks(&self, ctx: &Context<'_>) -> Result<Vec<Task>> {
+        let loader = ctx.data_unchecked::<DataLoader<TaskLoader>>();
+        let tasks = loader.load_one(self.id).await.map_err(|err| err.extend())?;
+        Ok(tasks.unwrap_or_default())
+    }
+
+    /// Number of tasks for the story
+    async fn task_count(&self, ctx: &Context<'_>) -> Result<usize> {
+        Ok(self.tasks(ctx).await?.len())
+    }
+
+    /// Number of completed tasks for the story
+    async fn complete_count(&self, ctx: &Context<'_>) -> Result<usize> {
+        let tasks = self.tasks(ctx).await?;
+        Ok(tasks
+            .iter()
+            .filter(|t| t.status == Status::Complete)
+            .count())
+    }
+}
+
+/// Graphql queries
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Get story by id
+    async fn story(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Story>> {
+        tracing::debug!("story: {}", id);
+        optional(api_ctx(ctx).story_repo.fetch(id).await)
+    }
+
+    /// Get stories by owner
+    async fn stories(&self, ctx: &Context<'_>, owner: Option<String>) -> Result<Vec<Story>> {
+        tracing::debug!("stories: {:?}", owner);
+        let owner = owner
+            .as_deref()
+            .map(normalize_owner)
+            .unwrap_or(BACKLOG.into());
+        let stories = api_ctx(ctx).story_repo.fetch_all(owner).await;
+        stories.map_err(|err| err.extend())
+    }
+
+    /// Get task by id
+    async fn task(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Task>> {
+        tracing::debug!("task: {}", id);
+        optional(api_ctx(ctx).task_repo.fetch(id).await)
+    }
+
+    /// Get incomplete tasks past their due date for an owner, most overdue first
+    async fn overdue_tasks(&self, ctx: &Context<'_>, owner: Option<String>) -> Result<Vec<Task>> {
+        tracing::debug!("overdue_tasks: {:?}", owner);
+        let owner = owner
+            .as_deref()
+            .map(normalize_owner)
+            .unwrap_or(BACKLOG.into());
+        let task_repo = &api_ctx(ctx).task_repo;
+        let tasks = task_repo.fetch_for_owner(&owner, None, Some(Utc::now()));
+        tasks.await.map_err(|err| err.extend())
+    }
+}
+
+/// Graphql mutations
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    /// Create a new story for an owner
+    async fn create_story(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        owner: Option<String>,
+    ) -> Result<Story> {
+        let body = CreateStoryBody { name, owner }.normalize();
+        tracing::debug!("create_story: {:?}", body);
+
+        let result = async {
+            body.validate()?;
+            let owner = body.owner.unwrap_or(BACKLOG.into());
+            api_ctx(ctx).story_repo.create(body.name, owner).await
+        };
+
+        result.await.map_err(|err| err.extend())
+    }
+
+    /// Update a story name and/or owner
+    async fn update_story(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        name: Option<String>,
+        owner: Option<String>,
+    ) -> Result<Story> {
+        let body = PatchStoryBody { name, owner }.normalize();
+        tracing::debug!("update_story: {}, {:?}", id, body);
+
+        let story_repo = &api_ctx(ctx).story_repo;
+        let result = async {
+            body.validate()?;
+            let story = story_repo.fetch(id).await?;
+            let (name, owner) = body.unwrap(story);
+            story_repo.update(id, name, owner).await
+        };
+
+        result.await.map_err(|err| err.extend())
+    }
+
+    /// Delete a story and its tasks
+    async fn delete_story(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        tracing::debug!("delete_story: {}", id);
+
+        let story_repo = &api_ctx(ctx).story_repo;
+        let result = async {
+            story_repo.fetch(id).await?;
+            story_repo.delete(id).await
+        };
+
+        result.await.map(|_| true).map_err(|err| err.extend())
+    }
+
+    /// Create a new task for a story
+    async fn create_task(
+        &self,
+        ctx: &Context<'_>,
+        story_id: Uuid,
+        name: String,
+        due_at: Option<DateTime<Utc>>,
+    ) -> Result<Task> {
+        let body = CreateTaskBody {
+            name,
+            story_id,
+            due_at,
+        }
+        .normalize();
+        tracing::debug!("create_task: {:?}", body);
+
+        let api_ctx = api_ctx(ctx);
+        let result = async {
+            body.validate()?;
+            api_ctx.story_repo.fetch(body.story_id).await?;
+            let task_repo = &api_ctx.task_repo;
+            task_repo
+                .create(body.story_id, body.name, body.due_at)
+                .await
+        };
+
+        result.await.map_err(|err| err.extend())
+    }
+
+    /// Update a task name, status and/or due date. A `null` due date removes it.
+    async fn update_task(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        name: Option<String>,
+        status: Option<Status>,
+        due_at: MaybeUndefined<DateTime<Utc>>,
+    ) -> Result<Task> {
+        let body = PatchTaskBody {
+            name,
+            status: status.map(|s| s.to_string()),
+            due_at: due_at.into(),
+        }
+        .normalize();
+        tracing::debug!("update_task: {}, {:?}", id, body);
+
+        let task_repo = &api_ctx(ctx).task_repo;
+        let result = async {
+            body.validate()?;
+            let task = task_repo.fetch(id).await?;
+            let (name, status, due_at) = body.unwrap(task);
+            task_repo.update(id, name, status, due_at).await
+        };
+
+        result.await.map_err(|err| err.extend())
+    }
+
+    /// Delete a task
+    async fn delete_task(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        tracing::debug!("delete_task: {}", id);
+
+        let task_repo = &api_ctx(ctx).task_repo;
+        let result = async {
+            task_repo.fetch(id).await?;
+            task_repo.delete(id).await
+        };
+
+        result.await.map(|_| true).map_err(|err| err.extend())
+    }
+}
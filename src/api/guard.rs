@@ -0,0 +1,164 @@
+use crate::{
+    api::{ApiCtx, IDEMPOTENT_REPLAYED_HEADER, SESSION_TOKEN_HEADER},
+    config::Config,
+    Error,
+};
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::{header, HeaderName},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::LengthLimitError;
+use std::{error::Error as _, sync::Arc};
+use tokio::time::{self, Instant};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// Build a CORS layer from config, or `None` when no origins are allowed.
+pub fn cors(config: &Config) -> Option<CorsLayer> {
+    if config.cors_allowed_origins.is_empty() {
+        return None;
+    }
+
+    let origins = if config.cors_allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(config.cors_allowed_origins.clone())
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(config.cors_allowed_methods.clone())
+            .allow_headers(config.cors_allowed_headers.clone())
+            .expose_headers([
+                header::RETRY_AFTER,
+                HeaderName::from_static("x-request-id"),
+                HeaderName::from_static(SESSION_TOKEN_HEADER),
+                HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+            ]),
+    )
+}
+
+/// Middleware that limits request body size and the time taken to read and handle a request.
+/// The body is buffered up front, so oversized and slow bodies are rejected before handlers run.
+pub async fn guard_request(
+    State(ctx): State<Arc<ApiCtx>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = ctx.config.max_body_size;
+    let timeout = ctx.config.request_timeout;
+    let deadline = Instant::now() + timeout;
+
+    let too_large = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok())
+        .is_some_and(|length| length > limit);
+    if too_large {
+        return Error::PayloadTooLarge { limit }.into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match time::timeout_at(deadline, body::to_bytes(body, limit)).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(err)) if is_length_limit(&err) => {
+            return Error::PayloadTooLarge { limit }.into_response()
+        }
+        Ok(Err(err)) => {
+            return Error::InvalidArgs {
+                messages: vec![format!("failed to read request body: {}", err)],
+            }
+            .into_response()
+        }
+        Err(_) => return Error::RequestTimeout.into_response(),
+    };
+
+    let request = Request::from_parts(parts, Body::from(bytes));
+    match time::timeout_at(deadline, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => Error::Timeout { after: timeout }.into_response(),
+    }
+}
+
+/// Check whether reading a body failed because it was over the size limit.
+fn is_length_limit(err: &axum::Error) -> bool {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if err.is::<LengthLimitError>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::api_ctx;
+    use axum::{http::StatusCode, middleware, routing::post, Router};
+    use futures_util::stream;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        let ctx = api_ctx();
+        Router::new()
+            .route("/echo", post(|body: String| async { body }))
+            .route(
+                "/slow",
+                post(|| async { time::sleep(Duration::from_secs(5)).await }),
+            )
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&ctx),
+                guard_request,
+            ))
+            .with_state(ctx)
+    }
+
+    async fn send(request: Request) -> (StatusCode, String) {
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn body_size_limit() {
+        let (status, body) = send(Request::post("/echo").body("ok".into()).unwrap()).await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "ok"));
+
+        // Rejected by content length
+        let request = Request::post("/echo").body(Body::from("x".repeat(2048)));
+        let (status, body) = send(request.unwrap()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.contains("request body exceeds 1024 bytes"));
+
+        // Rejected while streaming a body without a content length
+        let chunks = (0..2).map(|_| Ok::<_, std::io::Error>("x".repeat(600)));
+        let request = Request::post("/echo").body(Body::from_stream(stream::iter(chunks)));
+        let (status, _) = send(request.unwrap()).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_timeouts() {
+        let (status, body) = send(Request::post("/slow").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert!(body.contains("request timed out after 1s"));
+
+        let slow_body = stream::once(async {
+            time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, std::io::Error>("x")
+        });
+        let request = Request::post("/echo").body(Body::from_stream(slow_body));
+        let (status, _) = send(request.unwrap()).await;
+        assert_eq!(status, StatusCode::REQUEST_TIMEOUT);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    api::{limit, ApiCtx},
+    repo::{Claim, IdempotentRequest},
+    Error, Result,
+};
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Header carrying a client chosen key that makes retrying a create safe
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on responses replayed for an idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest idempotency key accepted
+const MAX_KEY_LEN: usize = 255;
+
+/// Create routes that honour idempotency keys
+const ROUTES: [&str; 2] = ["/stories", "/tasks"];
+
+/// Middleware that stores the response to a create sent with an idempotency key, and replays it
+/// when the same client retries the request with the same key and body.
+pub async fn idempotency(State(ctx): State<Arc<ApiCtx>>, request: Request, next: Next) -> Response {
+    let route = request.uri().path().to_owned();
+    if request.method() != Method::POST || !ROUTES.contains(&route.as_str()) {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER).cloned() else {
+        return next.run(request).await;
+    };
+
+    let client = limit::client_key(&request, &ctx.config.trusted_proxies);
+    match handle(&ctx, &client, &route, &key, request, next).await {
+        Ok(response) => response,
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Claim the key, then either run the request and store its response, or replay a stored one.
+async fn handle(
+    ctx: &ApiCtx,
+    client: &str,
+    route: &str,
+    key: &HeaderValue,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    let key = parse_key(key)?;
+    let (parts, body) = request.into_parts();
+    let bytes = body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|err| Error::InvalidArgs {
+            messages: vec![format!("failed to read request body: {}", err)],
+        })?;
+    let request_hash = Sha256::digest(&bytes);
+
+    let repo = &ctx.idempotency_repo;
+    let ttl = ctx.config.idempotency_ttl;
+    let stale_after = ctx.config.request_timeout;
+    match repo
+        .claim(client, route, key, &request_hash, ttl, stale_after)
+        .await?
+    {
+        Claim::Existing(existing) if existing.request_hash != request_hash.as_slice() => {
+            Err(Error::IdempotencyKeyReused)
+        }
+        Claim::Existing(existing) => replay(existing),
+        Claim::New => {
+            let response = next
+                .run(Request::from_parts(parts, Body::from(bytes)))
+                .await;
+            if !response.status().is_success() {
+                if let Err(err) = repo.release(client, route, key).await {
+                    tracing::warn!("failed to release idempotency key: {}", err);
+                }
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let bytes = body::to_bytes(body, usize::MAX)
+                .await
+                .map_err(|err| Error::Internal {
+                    message: format!("failed to read response body: {}", err),
+                })?;
+            let content_type = parts
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok());
+            // The create is done, so a failure here must not turn into an error for the client
+            if let Err(err) = repo
+                .complete(
+                    client,
+                    route,
+                    key,
+                    parts.status.as_u16(),
+                    content_type,
+                    &bytes,
+                )
+                .await
+            {
+                tracing::warn!("failed to store idempotent response: {}", err);
+            }
+            Ok(Response::from_parts(parts, Body::from(bytes)))
+        }
+    }
+}
+
+/// Check an idempotency key is printable ASCII and not too long.
+fn parse_key(key: &HeaderValue) -> Result<&str> {
+    key.to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| Error::InvalidArgs {
+            messages: vec![format!(
+                "{}: must be 1 to {} printable characters",
+                IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+            )],
+        })
+}
+
+/// Rebuild a stored response, or fail when the first request is still in progress.
+fn replay(existing: IdempotentRequest) -> Result<Response> {
+    let Some(status) = existing.status else {
+        return Err(Error::Conflict {
+            message: "a request with this idempotency key is in progress".into(),
+        });
+    };
+    let status = StatusCode::from_u16(status as u16).map_err(|err| Error::Internal {
+        message: format!("invalid stored status: {}", err),
+    })?;
+
+    let mut response = (status, existing.response.unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    match existing
+        .content_type
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+    {
+        Some(value) => headers.insert(header::CONTENT_TYPE, value),
+        None => headers.remove(header::CONTENT_TYPE),
+    };
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_keys() {
+        let key = HeaderValue::from_static("3f0c8a4e-8d4b-4c5e-9f1a-7b2d6e9c0a11");
+        assert_eq!(
+            parse_key(&key).unwrap(),
+            "3f0c8a4e-8d4b-4c5e-9f1a-7b2d6e9c0a11"
+        );
+        assert!(parse_key(&HeaderValue::from_static("")).is_err());
+        let key = HeaderValue::from_str(&"k".repeat(MAX_KEY_LEN + 1)).unwrap();
+        assert!(parse_key(&key).is_err());
+        let key = HeaderValue::from_bytes("clé".as_bytes()).unwrap();
+        assert!(parse_key(&key).is_err());
+    }
+
+    #[test]
+    fn reused_keys_have_their_own_code() {
+        let code: &'static str = (&Error::IdempotencyKeyReused).into();
+        assert_eq!(code, "idempotency_key_reused");
+        assert_eq!(
+            StatusCode::from(Error::IdempotencyKeyReused),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn replay_stored_responses() {
+        let existing = IdempotentRequest {
+            request_hash: vec![],
+            status: Some(201),
+            content_type: Some("application/json".into()),
+            response: Some(b"{}".to_vec()),
+        };
+        let response = replay(existing).unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+
+        let in_progress = IdempotentRequest {
+            request_hash: vec![],
+            status: None,
+            content_type: None,
+            response: None,
+        };
+        assert!(matches!(replay(in_progress), Err(Error::Conflict { .. })));
+    }
+}
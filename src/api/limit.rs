@@ -0,0 +1,266 @@
+use crate::{api::ApiCtx, config::RateLimit, tls::Principal, Error};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Header listing the addresses a request was forwarded for, appended to by each proxy
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Rate limiters for each class of route.
+pub struct RateLimiters {
+    pub reads: Option<RateLimiter>,
+    pub writes: Option<RateLimiter>,
+}
+
+impl RateLimiters {
+    /// Create limiters for the configured limits.
+    pub fn new(reads: Option<RateLimit>, writes: Option<RateLimit>) -> Self {
+        Self {
+            reads: reads.map(RateLimiter::new),
+            writes: writes.map(RateLimiter::new),
+        }
+    }
+
+    /// Drop buckets for clients that have been idle long enough to be full again.
+    pub fn purge(&self) {
+        self.reads
+            .iter()
+            .chain(self.writes.iter())
+            .for_each(RateLimiter::purge);
+    }
+}
+
+/// A token bucket per client key, holding up to `requests` tokens and refilling over `per`.
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+/// Tokens left for a client, as of the last update.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Constructor
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Tokens added per second
+    fn rate(&self) -> f64 {
+        self.limit.requests as f64 / self.limit.per.as_secs_f64()
+    }
+
+    /// Take a token for a client, or return how long until one is available.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let capacity = self.limit.requests as f64;
+        let rate = self.rate();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    /// Drop buckets that would be full by now, since they behave like new ones.
+    pub fn purge(&self) {
+        let now = Instant::now();
+        let capacity = self.limit.requests as f64;
+        let rate = self.rate();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * rate < capacity
+        });
+    }
+}
+
+/// Middleware that throttles clients, keyed by TLS principal or client IP. Reads and writes are
+/// limited separately.
+pub async fn rate_limit(State(ctx): State<Arc<ApiCtx>>, request: Request, next: Next) -> Response {
+    let (class, limiter) = match *request.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => ("read", &ctx.rate_limiters.reads),
+        _ => ("write", &ctx.rate_limiters.writes),
+    };
+
+    if let Some(limiter) = limiter {
+        let key = client_key(&request, &ctx.config.trusted_proxies);
+        if let Err(retry_after) = limiter.check(&key) {
+            tracing::debug!("rate limited {} request from {}", class, key);
+            metrics::counter!("gsd_rate_limited_total", "class" => class).increment(1);
+            return Error::RateLimited { retry_after }.into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Identify the client making a request. Only verified identities are used, never values the
+/// client could change on every request to get a fresh bucket: API keys and owners are chosen by
+/// the client, and a principal is not combined with its address, so a certificate shares one
+/// bucket wherever it connects from, while clients behind one NAT with their own certificates
+/// don't share theirs.
+pub(super) fn client_key(request: &Request, trusted_proxies: &[IpAddr]) -> String {
+    if let Some(Principal(principal)) = request.extensions().get::<Principal>() {
+        return format!("principal:{}", principal);
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", client_ip(request, addr.ip(), trusted_proxies)),
+        None => "ip:unknown".into(),
+    }
+}
+
+/// The client address. When the peer is a trusted proxy this is the last address it forwarded
+/// for that isn't another trusted proxy, since earlier entries can be made up by the client.
+fn client_ip(request: &Request, peer: IpAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let forwarded: Vec<&str> = request
+        .headers()
+        .get_all(FORWARDED_FOR_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut ip = peer;
+    for hop in forwarded.iter().rev() {
+        if !trusted_proxies.contains(&ip) {
+            break;
+        }
+        match hop.parse() {
+            Ok(addr) => ip = addr,
+            Err(_) => break,
+        }
+    }
+    ip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    #[test]
+    fn token_bucket_refills() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests: 2,
+            per: Duration::from_secs(1),
+        });
+        let start = Instant::now();
+
+        // Burst up to capacity, then wait for a refill
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_ok());
+        let retry_after = limiter.check_at("a", start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+
+        // Other clients have their own bucket
+        assert!(limiter.check_at("b", start).is_ok());
+
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_err());
+    }
+
+    fn from_peer(uri: &str, peer: &str, forwarded_for: Option<&str>) -> Request {
+        let mut request = Request::get(uri);
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header(FORWARDED_FOR_HEADER, forwarded_for);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        let addr: SocketAddr = peer.parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(addr));
+        request
+    }
+
+    #[test]
+    fn clients_keyed_by_principal_or_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let mut request = from_peer("/stories", "10.0.0.9:4000", None);
+        request.extensions_mut().insert(Principal("CN=carp".into()));
+        assert_eq!(client_key(&request, &[]), "principal:CN=carp");
+
+        // API keys and owners are chosen by the client, so they don't pick the bucket
+        let mut request = from_peer("/owners/carp/export?owner=carp", "10.0.0.9:4000", None);
+        request
+            .headers_mut()
+            .insert("x-api-key", "secret".parse().unwrap());
+        assert_eq!(client_key(&request, &[proxy]), "ip:10.0.0.9");
+    }
+
+    #[test]
+    fn principals_share_a_bucket_across_addresses() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests: 1,
+            per: Duration::from_secs(60),
+        });
+        let from = |principal: &str, peer: &str| {
+            let mut request = from_peer("/tasks", peer, None);
+            request
+                .extensions_mut()
+                .insert(Principal(principal.to_owned()));
+            client_key(&request, &[])
+        };
+
+        // Moving to another address doesn't refill the bucket
+        assert!(limiter.check(&from("CN=carp", "192.0.2.1:4000")).is_ok());
+        assert!(limiter.check(&from("CN=carp", "192.0.2.2:4000")).is_err());
+
+        // Another certificate behind the same address has its own
+        assert!(limiter.check(&from("CN=cobain", "192.0.2.1:4000")).is_ok());
+    }
+
+    #[test]
+    fn forwarded_for_only_from_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let forwarded = Some("1.2.3.4, 192.0.2.7");
+
+        // Untrusted peers can't pick their address
+        let request = from_peer("/stories", "192.0.2.99:4000", forwarded);
+        assert_eq!(client_key(&request, &[proxy]), "ip:192.0.2.99");
+
+        // The address the trusted proxy saw, not the one the client claimed
+        let request = from_peer("/stories", "10.0.0.1:4000", forwarded);
+        assert_eq!(client_key(&request, &[proxy]), "ip:192.0.2.7");
+
+        // Chains of trusted proxies are skipped
+        let inner: IpAddr = "10.0.0.2".parse().unwrap();
+        let request = from_peer("/stories", "10.0.0.1:4000", Some("192.0.2.7, 10.0.0.2"));
+        assert_eq!(client_key(&request, &[proxy, inner]), "ip:192.0.2.7");
+
+        // Garbage stops the search at the last good address
+        let request = from_peer("/stories", "10.0.0.1:4000", Some("not an ip"));
+        assert_eq!(client_key(&request, &[proxy]), "ip:10.0.0.1");
+    }
+}
@@ -1,12 +1,32 @@
-use axum::Router;
+use axum::{extract::DefaultBodyLimit, middleware, routing::MethodRouter, Router};
 use std::sync::Arc;
+use tower_http::catch_panic::CatchPanicLayer;
 
 mod ctx;
-mod dto;
+pub(crate) mod dto;
+mod extract;
+mod fallback;
+mod format;
+mod graphql;
+mod guard;
+mod idempotency;
+mod limit;
+mod openapi;
+mod owner;
+mod parse;
+mod recurrence;
+mod session;
 mod story;
 mod task;
 
 pub use ctx::ApiCtx;
+pub use fallback::not_found;
+pub use format::error_format;
+pub use idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+pub use limit::{RateLimiter, RateLimiters};
+pub use openapi::ApiDoc;
+pub use session::SESSION_TOKEN_HEADER;
+pub(crate) use story::BACKLOG;
 
 /// The top-level GSD web-service API
 pub struct Api {
@@ -21,6 +41,142 @@ impl Api {
 
     /// Define API routes, mapping paths to handlers.
     pub fn routes(self) -> Router {
-        story::routes().merge(task::routes()).with_state(self.ctx)
+        let docs_ui = self.ctx.config.api_docs;
+        let cors = guard::cors(&self.ctx.config);
+        let router = rest_routes()
+            .router
+            .merge(openapi::routes(docs_ui))
+            .merge(graphql::routes(docs_ui))
+            .fallback(fallback::not_found)
+            .layer(DefaultBodyLimit::max(self.ctx.config.max_body_size))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&self.ctx),
+                idempotency::idempotency,
+            ))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&self.ctx),
+                session::read_your_writes,
+            ))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&self.ctx),
+                guard::guard_request,
+            ))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&self.ctx),
+                limit::rate_limit,
+            ))
+            .layer(middleware::from_fn(fallback::method_not_allowed))
+            .layer(CatchPanicLayer::custom(fallback::panicked))
+            .with_state(self.ctx);
+
+        // Outermost, so rejections also carry CORS headers
+        match cors {
+            Some(cors) => router.layer(cors),
+            None => router,
+        }
+    }
+}
+
+/// The REST routes, which are all documented in the OpenAPI spec.
+fn rest_routes() -> Routes {
+    story::routes()
+        .merge(task::routes())
+        .merge(recurrence::routes())
+        .merge(owner::routes())
+}
+
+/// A router that keeps the paths it routes, so they can be checked against the OpenAPI spec.
+struct Routes {
+    router: Router<Arc<ApiCtx>>,
+    paths: Vec<&'static str>,
+}
+
+impl Routes {
+    /// Constructor
+    fn new() -> Self {
+        Self {
+            router: Router::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Route a path to handlers.
+    fn route(mut self, path: &'static str, method_router: MethodRouter<Arc<ApiCtx>>) -> Self {
+        self.router = self.router.route(path, method_router);
+        self.paths.push(path);
+        self
+    }
+
+    /// Add the routes of another set.
+    fn merge(mut self, other: Routes) -> Self {
+        self.router = self.router.merge(other.router);
+        self.paths.extend(other.paths);
+        self
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::{Config, ErrorFormat, LogFormat, NotifierKind, SmtpSecurity};
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+
+    /// Create an API context backed by a pool that fails fast, for tests that never reach the database.
+    pub fn api_ctx() -> Arc<ApiCtx> {
+        let config = Config {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            grpc_listen_addr: None,
+            admin_listen_addr: None,
+            db_max_connections: 1,
+            db_connect_timeout: Duration::ZERO,
+            db_acquire_timeout: Duration::from_millis(100),
+            db_host: "127.0.0.1".into(),
+            db_port: 1,
+            db_user: "postgres".into(),
+            db_password: "postgres".into(),
+            db_database: "postgres".into(),
+            db_schema: "public".into(),
+            db_replica_host: None,
+            db_replica_port: 1,
+            db_replica_lag: Duration::from_secs(5),
+            session_secret: None,
+            url_base: "/gsd/api/v1".into(),
+            api_docs: true,
+            rate_limit_reads: None,
+            rate_limit_writes: None,
+            trusted_proxies: vec![],
+            cors_allowed_origins: vec![],
+            cors_allowed_methods: vec![],
+            cors_allowed_headers: vec![],
+            max_body_size: 1024,
+            request_timeout: Duration::from_secs(1),
+            idempotency_ttl: Duration::from_secs(60),
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_client_ca_path: None,
+            tls_client_auth_required: false,
+            tls_reload_interval: Duration::from_secs(60),
+            shutdown_delay: Duration::ZERO,
+            shutdown_timeout: Duration::from_secs(1),
+            otlp_endpoint: None,
+            service_name: "gsd".into(),
+            log_format: LogFormat::Text,
+            error_format: ErrorFormat::Problem,
+            reminder_lead: Duration::from_secs(60 * 60),
+            reminder_interval: Duration::from_secs(60),
+            notifier: NotifierKind::Log,
+            smtp_host: None,
+            smtp_port: 25,
+            smtp_security: SmtpSecurity::None,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_from: None,
+        };
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy(&config.db_connection_string())
+            .unwrap();
+        Arc::new(ApiCtx::new(Arc::new(config), Arc::new(pool), None))
     }
 }
@@ -0,0 +1,260 @@
+use crate::{
+    api::{dto, owner, parse, recurrence, story, task, ApiCtx},
+    domain,
+    error::{ErrorDto, FieldError, ProblemDto},
+};
+use axum::{extract::State, response::Html, routing::get, Json, Router};
+use std::sync::Arc;
+use utoipa::{
+    openapi::{self, Server},
+    OpenApi,
+};
+
+/// The OpenAPI document for the GSD web-service API
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "GSD",
+        description = "An axum web-service that manages simplistic todo lists.\n\n\
+            Errors are `application/json` `ErrorDto` bodies by default, or \
+            `application/problem+json` `ProblemDto` bodies for requests sending \
+            `X-Error-Format: problem`, or when the server is configured with `ERROR_FORMAT=problem`."
+    ),
+    paths(
+        story::get_stories,
+        story::create_story,
+        story::import_story,
+        story::get_story,
+        story::update_story,
+        story::delete_story,
+        story::get_tasks,
+        task::create_task,
+        task::get_task,
+        task::update_task,
+        task::delete_task,
+        recurrence::get_recurrence,
+        recurrence::put_recurrence,
+        recurrence::delete_recurrence,
+        recurrence::skip_recurrence,
+        owner::export_stories,
+        owner::import_stories,
+        owner::get_owner_tasks,
+        owner::get_overdue_tasks,
+        owner::get_settings,
+        owner::put_settings,
+    ),
+    components(schemas(
+        domain::Story,
+        domain::Task,
+        domain::Status,
+        domain::Export,
+        domain::ExportStory,
+        domain::ExportTask,
+        domain::Recurrence,
+        domain::TaskRecurrence,
+        domain::Weekday,
+        domain::OwnerSettings,
+        dto::CreateStoryBody,
+        dto::PatchStoryBody,
+        dto::CreateTaskBody,
+        dto::PatchTaskBody,
+        dto::OwnerSettingsBody,
+        parse::Syntax,
+        ErrorDto,
+        ProblemDto,
+        FieldError,
+    )),
+    tags(
+        (name = "stories", description = "Stories and their tasks"),
+        (name = "tasks", description = "Tasks"),
+        (name = "owners", description = "An owner's stories, due tasks and settings"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Swagger UI page, loading the spec relative to the page location.
+const DOCS_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>GSD API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// API routes for the OpenAPI document and optional docs UI
+pub fn routes(docs_ui: bool) -> Router<Arc<ApiCtx>> {
+    let router = Router::new().route("/openapi.json", get(get_openapi));
+    if docs_ui {
+        router.route("/docs", get(get_docs))
+    } else {
+        router
+    }
+}
+
+/// Get the OpenAPI document, with the configured url base as server.
+async fn get_openapi(State(ctx): State<Arc<ApiCtx>>) -> Json<openapi::OpenApi> {
+    let mut spec = ApiDoc::openapi();
+    spec.servers = Some(vec![Server::new(&ctx.config.url_base)]);
+    Json(spec)
+}
+
+/// Get the docs UI
+async fn get_docs() -> Html<&'static str> {
+    Html(DOCS_HTML)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{dto::MAX_LEN, rest_routes, tests, Api};
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use std::collections::BTreeSet;
+    use tower::ServiceExt;
+    use utoipa::openapi::PathItemType;
+
+    /// Methods checked against every routed path
+    const METHODS: [(Method, PathItemType); 5] = [
+        (Method::GET, PathItemType::Get),
+        (Method::POST, PathItemType::Post),
+        (Method::PUT, PathItemType::Put),
+        (Method::PATCH, PathItemType::Patch),
+        (Method::DELETE, PathItemType::Delete),
+    ];
+
+    /// Map a route's segments, e.g. to fill in or rename its path params.
+    fn map_params(route: &str, f: impl Fn(&str) -> String) -> String {
+        let segments: Vec<String> = route
+            .split('/')
+            .map(|s| match s.strip_prefix(':') {
+                Some(param) => f(param),
+                None => s.to_owned(),
+            })
+            .collect();
+        segments.join("/")
+    }
+
+    #[tokio::test]
+    async fn spec_matches_router() {
+        let router = Api::new(tests::api_ctx()).routes();
+        let spec = ApiDoc::openapi();
+
+        // The spec documents exactly the REST paths the router serves...
+        let routes = rest_routes().paths;
+        let documented: BTreeSet<String> = spec.paths.paths.keys().cloned().collect();
+        let routed: BTreeSet<String> = routes
+            .iter()
+            .map(|route| map_params(route, |param| format!("{{{}}}", param)))
+            .collect();
+        assert_eq!(documented, routed);
+
+        // ...and exactly the routed methods on each path.
+        for route in routes {
+            let path = map_params(route, |param| format!("{{{}}}", param));
+            let item = &spec.paths.paths[&path];
+
+            // Fill path params with a value that never reaches the database.
+            let uri = map_params(route, |_| "x".into());
+
+            for (method, item_type) in METHODS {
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(&uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let status = router.clone().oneshot(request).await.unwrap().status();
+                let is_routed =
+                    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED;
+                let is_documented = item.operations.contains_key(&item_type);
+
+                assert!(
+                    is_routed || !is_documented,
+                    "{} {} documented but not routed",
+                    method,
+                    path
+                );
+                assert!(
+                    is_documented || !is_routed,
+                    "{} {} routed but not documented",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn spec_has_every_negotiated_format() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/stories/{id}", "/stories/{id}/tasks"] {
+            let content = &spec["paths"][path]["get"]["responses"]["200"]["content"];
+            assert!(
+                content["application/json"]["schema"].is_object(),
+                "{}",
+                path
+            );
+            assert!(content["text/csv"]["schema"].is_object(), "{}", path);
+            assert!(content["text/markdown"]["schema"].is_object(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn errors_are_legacy_or_problem_details() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut errors = 0;
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                for (status, response) in operation["responses"].as_object().unwrap() {
+                    if !status.starts_with('4') && !status.starts_with('5') {
+                        continue;
+                    }
+                    let content = &response["content"];
+                    let schema = |content_type: &str| {
+                        content[content_type]["schema"]["$ref"]
+                            .as_str()
+                            .map(str::to_owned)
+                    };
+                    let operation = format!("{} {} {}", method, path, status);
+                    assert_eq!(
+                        schema("application/json").as_deref(),
+                        Some("#/components/schemas/ErrorDto"),
+                        "{}",
+                        operation
+                    );
+                    assert_eq!(
+                        schema("application/problem+json").as_deref(),
+                        Some("#/components/schemas/ProblemDto"),
+                        "{}",
+                        operation
+                    );
+                    errors += 1;
+                }
+            }
+        }
+        assert!(errors > 0);
+    }
+
+    #[test]
+    fn spec_has_validation_constraints() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        let name = &schemas["CreateStoryBody"]["properties"]["name"];
+        assert_eq!(name["minLength"], 1);
+        assert_eq!(name["maxLength"], MAX_LEN);
+
+        let status = &schemas["Status"]["enum"];
+        assert_eq!(status, &serde_json::json!(["incomplete", "complete"]));
+    }
+}
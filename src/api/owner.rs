@@ -0,0 +1,313 @@
+use crate::{
+    api::{
+        dto::{
+            normalize_name, CreateStoryBody, CreateTaskBody, ExportParams, ImportParams, Normalize,
+            OwnerPath, OwnerSettingsBody, OwnerTasksParams,
+        },
+        extract::{Json, Path, Query},
+        ApiCtx, Routes,
+    },
+    domain::{Export, OwnerSettings, Task, EXPORT_VERSION},
+    error::{field_errors, FieldError},
+    Error, Result,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use validator::Validate;
+
+/// API routes for owners
+pub(super) fn routes() -> Routes {
+    Routes::new()
+        .route("/owners/:owner/export", get(export_stories))
+        .route("/owners/:owner/import", post(import_stories))
+        .route("/owners/:owner/tasks", get(get_owner_tasks))
+        .route("/owners/:owner/tasks/overdue", get(get_overdue_tasks))
+        .route(
+            "/owners/:owner/settings",
+            get(get_settings).put(put_settings),
+        )
+}
+
+/// Get incomplete tasks for an owner, soonest due first
+#[utoipa::path(
+    get,
+    path = "/owners/{owner}/tasks",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner"), OwnerTasksParams),
+    responses(
+        (status = 200, description = "The incomplete tasks", body = [Task]),
+        (status = 400, description = "Invalid owner or duration", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn get_owner_tasks(
+    Path(owner): Path<String>,
+    Query(params): Query<OwnerTasksParams>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<Vec<Task>>> {
+    tracing::debug!("get_owner_tasks: {}, {:?}", owner, params);
+
+    let owner = owner_path(&owner)?;
+    let (due_from, due_before) = match params.due_within.as_deref() {
+        Some(due_within) => {
+            let now = Utc::now();
+            (Some(now), Some(due_before(now, due_within)?))
+        }
+        None => (None, None),
+    };
+
+    let tasks = ctx
+        .task_repo
+        .fetch_for_owner(&owner, due_from, due_before)
+        .await?;
+    Ok(Json(tasks))
+}
+
+/// Get incomplete tasks for an owner that are past their due date, most overdue first
+#[utoipa::path(
+    get,
+    path = "/owners/{owner}/tasks/overdue",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner")),
+    responses(
+        (status = 200, description = "The overdue tasks", body = [Task]),
+        (status = 400, description = "Invalid owner", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn get_overdue_tasks(
+    Path(owner): Path<String>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<Vec<Task>>> {
+    tracing::debug!("get_overdue_tasks: {}", owner);
+
+    let owner = owner_path(&owner)?;
+    let tasks = ctx
+        .task_repo
+        .fetch_for_owner(&owner, None, Some(Utc::now()))
+        .await?;
+    Ok(Json(tasks))
+}
+
+/// Get settings for an owner
+#[utoipa::path(
+    get,
+    path = "/owners/{owner}/settings",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner")),
+    responses(
+        (status = 200, description = "The owner settings", body = OwnerSettings),
+        (status = 400, description = "Invalid owner", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn get_settings(
+    Path(owner): Path<String>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<OwnerSettings>> {
+    tracing::debug!("get_settings: {}", owner);
+
+    let settings = ctx.owner_repo.fetch(&owner_path(&owner)?).await?;
+    Ok(Json(settings))
+}
+
+/// Set the time zone and reminder email for an owner
+#[utoipa::path(
+    put,
+    path = "/owners/{owner}/settings",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner")),
+    request_body = OwnerSettingsBody,
+    responses(
+        (status = 200, description = "The owner settings", body = OwnerSettings),
+        (status = 400, description = "Invalid arguments", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn put_settings(
+    Path(owner): Path<String>,
+    State(ctx): State<Arc<ApiCtx>>,
+    Json(body): Json<OwnerSettingsBody>,
+) -> Result<Json<OwnerSettings>> {
+    tracing::debug!("put_settings: {}, {:?}", owner, body);
+
+    let owner = owner_path(&owner)?;
+    let body = body.normalize();
+    body.validate()?;
+
+    let settings = ctx.owner_repo.save(body.unwrap(owner)).await?;
+    Ok(Json(settings))
+}
+
+/// Normalize and validate an owner from the path, as when creating stories.
+fn owner_path(owner: &str) -> Result<String> {
+    let path = OwnerPath {
+        owner: owner.to_owned(),
+    }
+    .normalize();
+    path.validate()?;
+    Ok(path.owner)
+}
+
+/// The end of a `due_within` window starting now, e.g. `2d`.
+fn due_before(now: DateTime<Utc>, due_within: &str) -> Result<DateTime<Utc>> {
+    humantime::parse_duration(due_within)
+        .ok()
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .and_then(|duration| now.checked_add_signed(duration))
+        .ok_or_else(|| Error::ValidationFailed {
+            fields: vec![FieldError::new(
+                "due_within",
+                "duration",
+                "invalid duration",
+            )],
+        })
+}
+
+/// Export all stories and tasks for an owner
+#[utoipa::path(
+    get,
+    path = "/owners/{owner}/export",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner"), ExportParams),
+    responses(
+        (status = 200, description = "The export document", body = Export),
+        (status = 400, description = "Invalid owner", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn export_stories(
+    Path(owner): Path<String>,
+    Query(params): Query<ExportParams>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<Export>> {
+    tracing::debug!("export_stories: {}, {:?}", owner, params);
+
+    let owner = owner_path(&owner)?;
+    let include_deleted = params.include_deleted.unwrap_or(false);
+
+    let export = ctx.export_repo.export(owner, include_deleted).await?;
+    Ok(Json(export))
+}
+
+/// Import stories and tasks from an export document for an owner
+#[utoipa::path(
+    post,
+    path = "/owners/{owner}/import",
+    tag = "owners",
+    params(("owner" = String, Path, description = "Story owner"), ImportParams),
+    request_body = Export,
+    responses(
+        (status = 201, description = "The imported stories and tasks", body = Export),
+        (status = 400, description = "Invalid document", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn import_stories(
+    Path(owner): Path<String>,
+    Query(params): Query<ImportParams>,
+    State(ctx): State<Arc<ApiCtx>>,
+    Json(body): Json<Export>,
+) -> Result<impl IntoResponse> {
+    tracing::debug!("import_stories: {}, {:?}", owner, params);
+
+    let owner = owner_path(&owner)?;
+    let body = normalize_import(body);
+    validate_import(&owner, &body)?;
+
+    let preserve_ids = params.preserve_ids.unwrap_or(false);
+
+    let export = ctx.export_repo.import(owner, body, preserve_ids).await?;
+    Ok((StatusCode::CREATED, Json(export)))
+}
+
+/// Normalize story and task names in an export document, as when creating them.
+fn normalize_import(mut export: Export) -> Export {
+    for story in &mut export.stories {
+        story.name = normalize_name(&story.name);
+        for task in &mut story.tasks {
+            task.name = normalize_name(&task.name);
+        }
+    }
+    export
+}
+
+/// Validate an export document with the same rules used when creating stories and tasks.
+fn validate_import(owner: &str, export: &Export) -> Result<()> {
+    let mut fields = Vec::new();
+    if export.version != EXPORT_VERSION {
+        let error = FieldError::new("version", "unsupported_version", "unsupported version")
+            .with_param("supported", EXPORT_VERSION);
+        fields.push(error);
+    }
+
+    for (i, story) in export.stories.iter().enumerate() {
+        let body = CreateStoryBody {
+            name: story.name.clone(),
+            owner: Some(owner.to_owned()),
+        };
+        if let Err(errors) = body.validate() {
+            fields.extend(field_errors(&format!("stories[{}].", i), &errors));
+        }
+        for (j, task) in story.tasks.iter().enumerate() {
+            let body = CreateTaskBody {
+                name: task.name.clone(),
+                story_id: story.id,
+                due_at: task.due_at,
+            };
+            if let Err(errors) = body.validate() {
+                let prefix = format!("stories[{}].tasks[{}].", i, j);
+                fields.extend(field_errors(&prefix, &errors));
+            }
+        }
+    }
+
+    if fields.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ValidationFailed { fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{tests, Api};
+    use axum::{body::Body, extract::Request};
+    use tower::ServiceExt;
+
+    #[test]
+    fn due_within_windows() {
+        let now: DateTime<Utc> = "2026-10-19T09:00:00Z".parse().unwrap();
+        assert_eq!(
+            due_before(now, "1d 2h").unwrap(),
+            "2026-10-20T11:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        for invalid in ["soon", "-1h", "", "99999999999999999d"] {
+            assert!(due_before(now, invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn owners_in_paths_are_validated() {
+        assert_eq!(owner_path(" Alice ").unwrap(), "alice");
+        for invalid in ["  ", "a\tb", &"a".repeat(101)] {
+            match owner_path(invalid) {
+                Err(Error::ValidationFailed { fields }) => assert_eq!(fields[0].field, "owner"),
+                other => panic!("expected validation error for {:?}: {:?}", invalid, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn long_owners_are_rejected_before_writing() {
+        // Without a database, only validation can answer
+        let router = Api::new(tests::api_ctx()).routes();
+        let request = Request::put(format!("/owners/{}/settings", "a".repeat(101)))
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{domain::Status, error::FieldError, Error, Result};
+use axum::http::{header, HeaderMap};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Plain text list syntaxes that can be imported as a story.
+#[derive(Debug, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum Syntax {
+    #[serde(rename = "todotxt")]
+    TodoTxt,
+    #[serde(rename = "markdown")]
+    Markdown,
+}
+
+impl Syntax {
+    /// Markdown when the content type says so, otherwise todo.txt.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        if content_type
+            .to_ascii_lowercase()
+            .starts_with("text/markdown")
+        {
+            Syntax::Markdown
+        } else {
+            Syntax::TodoTxt
+        }
+    }
+}
+
+/// The outcome of parsing a line, with an error code and message for invalid lines.
+type LineResult = std::result::Result<(), (&'static str, &'static str)>;
+
+/// A list item parsed from a single line of text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Item {
+    pub line: usize,
+    pub name: String,
+    pub status: Status,
+    pub priority: Option<char>,
+    pub projects: Vec<String>,
+}
+
+/// A parsed list with an optional title.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Checklist {
+    pub title: Option<String>,
+    pub items: Vec<Item>,
+}
+
+impl Checklist {
+    /// Parse text in the given syntax, keeping items in file order and collecting a field error
+    /// for every invalid line.
+    pub fn parse(syntax: Syntax, text: &str) -> Result<Self> {
+        let mut checklist = Checklist::default();
+        let mut fields = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let result = match syntax {
+                Syntax::TodoTxt => checklist.push_todo_txt(i + 1, line),
+                Syntax::Markdown => checklist.push_markdown(i + 1, line),
+            };
+            if let Err((code, message)) = result {
+                fields.push(FieldError::new(format!("lines[{}]", i + 1), code, message));
+            }
+        }
+
+        if !fields.is_empty() {
+            return Err(Error::ValidationFailed { fields });
+        }
+
+        Ok(checklist)
+    }
+
+    /// The first project tag found on any item.
+    pub fn project(&self) -> Option<&str> {
+        self.items
+            .iter()
+            .flat_map(|item| item.projects.iter())
+            .map(String::as_str)
+            .next()
+    }
+
+    /// Parse a todo.txt line: `x (A) 2024-03-01 2024-02-22 description +project @context`.
+    fn push_todo_txt(&mut self, line: usize, text: &str) -> LineResult {
+        let mut rest = text.trim();
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        let mut status = Status::Incomplete;
+        if let Some(after) = rest.strip_prefix("x ") {
+            status = Status::Complete;
+            rest = after.trim_start();
+        }
+
+        let mut priority = None;
+        if let Some((p, after)) = todo_priority(rest) {
+            priority = Some(p);
+            rest = after;
+        }
+
+        // Completion and creation dates
+        for _ in 0..2 {
+            match rest.split_once(' ') {
+                Some((word, after)) if is_date(word) => rest = after.trim_start(),
+                _ => break,
+            }
+        }
+
+        let mut projects = Vec::new();
+        for word in rest.split_whitespace() {
+            if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                projects.push(project.to_owned());
+            }
+            if let Some(p) = word.strip_prefix("pri:").and_then(priority_char) {
+                priority = priority.or(Some(p));
+            }
+        }
+
+        if rest.is_empty() {
+            return Err(("required", "missing description"));
+        }
+
+        self.items.push(Item {
+            line,
+            name: rest.to_owned(),
+            status,
+            priority,
+            projects,
+        });
+
+        Ok(())
+    }
+
+    /// Parse a markdown line: a heading or a `- [ ] description` checklist item.
+    fn push_markdown(&mut self, line: usize, text: &str) -> LineResult {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        if text.starts_with('#') {
+            let heading = text.trim_start_matches('#').trim();
+            if self.title.is_none() && !heading.is_empty() {
+                self.title = Some(heading.to_owned());
+            }
+            return Ok(());
+        }
+
+        let item = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| text.strip_prefix(bullet))
+            .ok_or(("checklist_item", "expected a checklist item"))?
+            .trim_start();
+
+        let (status, name) = if let Some(name) = item.strip_prefix("[ ]") {
+            (Status::Incomplete, name)
+        } else if let Some(name) = item
+            .strip_prefix("[x]")
+            .or_else(|| item.strip_prefix("[X]"))
+        {
+            (Status::Complete, name)
+        } else {
+            return Err((
+                "completion_marker",
+                "expected a [ ] or [x] completion marker",
+            ));
+        };
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(("required", "missing description"));
+        }
+
+        self.items.push(Item {
+            line,
+            name: name.to_owned(),
+            status,
+            priority: None,
+            projects: Vec::new(),
+        });
+
+        Ok(())
+    }
+}
+
+/// Split a leading `(A) ` priority from a todo.txt line.
+fn todo_priority(text: &str) -> Option<(char, &str)> {
+    let after = text.strip_prefix('(')?;
+    let (p, after) = after.split_once(") ")?;
+    let p = priority_char(p)?;
+    Some((p, after.trim_start()))
+}
+
+/// A priority is a single uppercase letter.
+fn priority_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+        _ => None,
+    }
+}
+
+/// Check for a `YYYY-MM-DD` date.
+fn is_date(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_todo_txt() {
+        let text = "\
+            x 2024-03-01 2024-02-22 Read Suttree +books\n\
+            \n\
+            Read Outer Dark @home\n\
+            (A) 2024-02-22 Read Blood Meridian +books +western\n";
+
+        let checklist = Checklist::parse(Syntax::TodoTxt, text).unwrap();
+        assert_eq!(checklist.project(), Some("books"));
+
+        // Items keep their file order, whatever their priority
+        let names: Vec<&str> = checklist.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Read Suttree +books",
+                "Read Outer Dark @home",
+                "Read Blood Meridian +books +western",
+            ]
+        );
+        assert_eq!(checklist.items[0].status, Status::Complete);
+        assert_eq!(checklist.items[1].status, Status::Incomplete);
+        assert_eq!(checklist.items[2].priority, Some('A'));
+        assert_eq!(checklist.items[2].line, 4);
+    }
+
+    #[test]
+    fn parse_markdown() {
+        let text = "# Books To Read\n\n- [x] Suttree\n* [ ] Outer Dark\n  - [X] Child of God\n";
+
+        let checklist = Checklist::parse(Syntax::Markdown, text).unwrap();
+        assert_eq!(checklist.title.as_deref(), Some("Books To Read"));
+        assert_eq!(checklist.items.len(), 3);
+        assert_eq!(checklist.items[0].status, Status::Complete);
+        assert_eq!(checklist.items[1].status, Status::Incomplete);
+        assert_eq!(checklist.items[2].name, "Child of God");
+    }
+
+    #[test]
+    fn parse_errors_per_line() {
+        let text = "- [x] Suttree\nsome prose\n- Outer Dark\n- [ ]\n";
+
+        let err = Checklist::parse(Syntax::Markdown, text).unwrap_err();
+        match err {
+            Error::ValidationFailed { fields } => assert_eq!(
+                fields,
+                vec![
+                    FieldError::new("lines[2]", "checklist_item", "expected a checklist item"),
+                    FieldError::new(
+                        "lines[3]",
+                        "completion_marker",
+                        "expected a [ ] or [x] completion marker"
+                    ),
+                    FieldError::new("lines[4]", "required", "missing description"),
+                ]
+            ),
+            _ => panic!("expected validation failed error"),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    api::{
+        extract::{Json, Path},
+        ApiCtx, Routes,
+    },
+    domain::{Recurrence, TaskRecurrence},
+    Error, Result,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// API routes for task recurrence rules
+pub(super) fn routes() -> Routes {
+    Routes::new()
+        .route(
+            "/tasks/:id/recurrence",
+            get(get_recurrence)
+                .put(put_recurrence)
+                .delete(delete_recurrence),
+        )
+        .route("/tasks/:id/recurrence/skip", post(skip_recurrence))
+}
+
+/// Get the recurrence rule for a task
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/recurrence",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "The recurrence rule", body = TaskRecurrence),
+        (status = 404, description = "Task does not recur", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn get_recurrence(
+    Path(id): Path<Uuid>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<TaskRecurrence>> {
+    tracing::debug!("get_recurrence: {}", id);
+
+    let recurrence = ctx.recurrence_repo.fetch(id).await?;
+    Ok(Json(recurrence))
+}
+
+/// Make a task recur, or change its rule. The next occurrence is created when the task is
+/// completed, or at the next time the rule occurs in the owner's time zone, whichever is first.
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}/recurrence",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    request_body = Recurrence,
+    responses(
+        (status = 200, description = "The recurrence rule", body = TaskRecurrence),
+        (status = 400, description = "Invalid rule", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 404, description = "Task not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn put_recurrence(
+    Path(id): Path<Uuid>,
+    State(ctx): State<Arc<ApiCtx>>,
+    Json(rule): Json<Recurrence>,
+) -> Result<Json<TaskRecurrence>> {
+    tracing::debug!("put_recurrence: {}, {:?}", id, rule);
+
+    rule.validate()?;
+    let rule = rule.normalize();
+    let task = ctx.task_repo.fetch(id).await?;
+    let story = ctx.story_repo.fetch(task.story_id).await?;
+    let settings = ctx.owner_repo.fetch(&story.owner).await?;
+
+    let next_at = rule
+        .next_after(&Utc::now().with_timezone(&settings.timezone))
+        .ok_or_else(|| Error::Internal {
+            message: format!("no next occurrence for rule: {}", rule),
+        })?;
+    let recurrence = ctx
+        .recurrence_repo
+        .save(id, rule, next_at.with_timezone(&Utc))
+        .await?;
+
+    Ok(Json(recurrence))
+}
+
+/// Stop a task recurring
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}/recurrence",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    responses(
+        (status = 204, description = "The task no longer recurs"),
+        (status = 404, description = "Task does not recur", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn delete_recurrence(
+    Path(id): Path<Uuid>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<StatusCode> {
+    tracing::debug!("delete_recurrence: {}", id);
+
+    match ctx.recurrence_repo.delete(id).await? {
+        0 => Err(Error::NotFound {
+            message: format!("task recurrence not found: {}", id),
+        }),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// Skip the next occurrence of a recurring task
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/recurrence/skip",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "The recurrence rule, with the next occurrence moved on", body = TaskRecurrence),
+        (status = 404, description = "Task does not recur", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn skip_recurrence(
+    Path(id): Path<Uuid>,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Json<TaskRecurrence>> {
+    tracing::debug!("skip_recurrence: {}", id);
+
+    let recurrence = ctx.recurrence_repo.skip(id, Utc::now()).await?;
+    Ok(Json(recurrence))
+}
@@ -0,0 +1,135 @@
+use crate::{api::ApiCtx, config::Secret, repo::read_primary};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Header carrying the time of a client's last write, returned by writes and sent back by clients
+pub const SESSION_TOKEN_HEADER: &str = "x-session-token";
+
+/// Path of the GraphQL endpoint, whose handler tells queries and mutations apart
+pub(super) const GRAPHQL_PATH: &str = "/graphql";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Middleware for read-your-writes with a read replica. Writes read from the primary and return a
+/// signed session token; reads that send a token younger than the replica lag also use the
+/// primary.
+pub async fn read_your_writes(
+    State(ctx): State<Arc<ApiCtx>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(secret) = secret(&ctx) else {
+        return next.run(request).await;
+    };
+
+    let write = !request.method().is_safe() && request.uri().path() != GRAPHQL_PATH;
+    let recent_write = request
+        .headers()
+        .get(SESSION_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|token| verify(secret, token))
+        .is_some_and(|written_at| is_recent(written_at, now_millis(), ctx.config.db_replica_lag));
+
+    if !write && !recent_write {
+        return next.run(request).await;
+    }
+
+    let mut response = read_primary(next.run(request)).await;
+    if write && response.status().is_success() {
+        issue_token(&ctx, response.headers_mut());
+    }
+    response
+}
+
+/// Add a session token for a write made now, when reads may use a replica.
+pub(super) fn issue_token(ctx: &ApiCtx, headers: &mut HeaderMap) {
+    if let Some(secret) = secret(ctx) {
+        let token = sign(secret, now_millis());
+        if let Ok(value) = HeaderValue::from_str(&token) {
+            headers.insert(SESSION_TOKEN_HEADER, value);
+        }
+    }
+}
+
+/// The key session tokens are signed with, when reads may use a replica.
+fn secret(ctx: &ApiCtx) -> Option<&Secret> {
+    ctx.config.db_replica_host.as_ref()?;
+    ctx.config.session_secret.as_ref()
+}
+
+/// Milliseconds since the unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// A token for a write time, as `<millis>.<hex hmac>`, so clients can't make one up.
+fn sign(secret: &Secret, written_at: u64) -> String {
+    let signature = mac(secret, written_at).finalize().into_bytes();
+    format!("{}.{}", written_at, hex::encode(signature))
+}
+
+/// The write time in a token, if it was signed with the secret.
+fn verify(secret: &Secret, token: &str) -> Option<u64> {
+    let (written_at, signature) = token.split_once('.')?;
+    let written_at = written_at.parse().ok()?;
+    let signature = hex::decode(signature).ok()?;
+    mac(secret, written_at).verify_slice(&signature).ok()?;
+    Some(written_at)
+}
+
+/// A MAC over a write time.
+fn mac(secret: &Secret, written_at: u64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.expose().as_bytes())
+        .expect("HMAC takes keys of any length");
+    mac.update(&written_at.to_be_bytes());
+    mac
+}
+
+/// Whether a write at the given time may not have reached the replica yet. Clock skew between
+/// servers puts some writes in the future, but never by more than the lag.
+fn is_recent(written_at: u64, now: u64, lag: Duration) -> bool {
+    now.abs_diff(written_at) < lag.as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_writes_within_replica_lag() {
+        let lag = Duration::from_secs(5);
+        assert!(is_recent(10_000, 10_000, lag));
+        assert!(is_recent(10_000, 14_999, lag));
+        assert!(!is_recent(10_000, 15_000, lag));
+        // Clock skew between servers puts some writes in the future
+        assert!(is_recent(11_000, 10_000, lag));
+        assert!(!is_recent(u64::MAX, 10_000, lag));
+    }
+
+    #[test]
+    fn tokens_are_signed() {
+        let secret = Secret::from("s3cret");
+        let token = sign(&secret, 10_000);
+        assert_eq!(verify(&secret, &token), Some(10_000));
+
+        // Tokens signed with another key, edited, or unsigned are ignored
+        assert_eq!(verify(&Secret::from("other"), &token), None);
+        let (_, signature) = token.split_once('.').unwrap();
+        assert_eq!(verify(&secret, &format!("99999.{}", signature)), None);
+        assert_eq!(verify(&secret, "10000"), None);
+        assert_eq!(verify(&secret, "10000.zz"), None);
+    }
+}
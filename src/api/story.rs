@@ -1,17 +1,23 @@
 use crate::{
     api::{
-        dto::{CreateStoryBody, GetStoriesParams, PatchStoryBody},
-        ApiCtx,
+        dto::{
+            normalize_owner, CreateStoryBody, CreateTaskBody, GetStoriesParams, ImportStoryParams,
+            Normalize, PatchStoryBody,
+        },
+        extract::{Json, Path, Query},
+        format::{self, Format},
+        parse::{Checklist, Syntax},
+        ApiCtx, Routes,
     },
-    domain::{Story, Task},
-    Result,
+    domain::{Export, ExportStory, ExportTask, Story, Task, EXPORT_VERSION},
+    error::{field_errors, FieldError},
+    Error, Result,
 };
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::get,
-    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
 };
 use futures_util::TryFutureExt;
 use std::sync::Arc;
@@ -19,12 +25,13 @@ use uuid::Uuid;
 use validator::Validate;
 
 /// Default owner for stories
-const BACKLOG: &str = "backlog";
+pub const BACKLOG: &str = "backlog";
 
 /// API routes for stories
-pub fn routes() -> Router<Arc<ApiCtx>> {
-    Router::new()
+pub(super) fn routes() -> Routes {
+    Routes::new()
         .route("/stories", get(get_stories).post(create_story))
+        .route("/stories/import", post(import_story))
         .route("/stories/:id/tasks", get(get_tasks))
         .route(
             "/stories/:id",
@@ -32,50 +39,126 @@ pub fn routes() -> Router<Arc<ApiCtx>> {
         )
 }
 
-/// Get story by id
-async fn get_story(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> Result<Json<Story>> {
-    log::debug!("get_story: {}", id);
+/// Get story by id, or its tasks as CSV or a markdown checklist when requested.
+#[utoipa::path(
+    get,
+    path = "/stories/{id}",
+    tag = "stories",
+    params(("id" = Uuid, Path, description = "Story id")),
+    responses(
+        (status = 200, description = "The story, its tasks as CSV, or the story as a checklist", content(
+            ("application/json" = Story),
+            ("text/csv" = String),
+            ("text/markdown" = String),
+        )),
+        (status = 400, description = "Invalid id", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 404, description = "Story not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 406, description = "No acceptable format", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn get_story(
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    State(ctx): State<Arc<ApiCtx>>,
+) -> Result<Response> {
+    tracing::debug!("get_story: {}", id);
+
+    let format = Format::from_headers(&headers)?;
     let story = ctx.story_repo.fetch(id).await?;
-    Ok(Json(story))
+
+    let response = match format {
+        Format::Json => Json(story).into_response(),
+        Format::Csv => format::csv(&ctx.task_repo.fetch_all(id).await?),
+        Format::Markdown => format::markdown(&story, &ctx.task_repo.fetch_all(id).await?),
+    };
+
+    Ok(format::vary_accept(response))
 }
 
 /// Get stories by owner
+#[utoipa::path(
+    get,
+    path = "/stories",
+    tag = "stories",
+    params(GetStoriesParams),
+    responses((status = 200, description = "Stories for the owner", body = [Story]))
+)]
 async fn get_stories(
-    params: Option<Query<GetStoriesParams>>,
+    Query(params): Query<GetStoriesParams>,
     State(ctx): State<Arc<ApiCtx>>,
 ) -> Result<Json<Vec<Story>>> {
-    log::debug!("get_stories: {:?}", params);
+    tracing::debug!("get_stories: {:?}", params);
 
-    let Query(params) = params.unwrap_or_default();
-    let owner = params.owner.unwrap_or(BACKLOG.into());
+    let owner = params
+        .owner
+        .as_deref()
+        .map(normalize_owner)
+        .unwrap_or(BACKLOG.into());
 
     let stories = ctx.story_repo.fetch_all(owner).await?;
     Ok(Json(stories))
 }
 
-/// Get tasks for a story
+/// Get tasks for a story as JSON, CSV or a markdown checklist.
+#[utoipa::path(
+    get,
+    path = "/stories/{id}/tasks",
+    tag = "stories",
+    params(("id" = Uuid, Path, description = "Story id")),
+    responses(
+        (status = 200, description = "Tasks for the story, as CSV or as a checklist", content(
+            ("application/json" = [Task]),
+            ("text/csv" = String),
+            ("text/markdown" = String),
+        )),
+        (status = 404, description = "Story not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 406, description = "No acceptable format", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn get_tasks(
     Path(story_id): Path<Uuid>,
+    headers: HeaderMap,
     State(ctx): State<Arc<ApiCtx>>,
-) -> Result<Json<Vec<Task>>> {
-    log::debug!("get_tasks: story_id = {}", story_id);
+) -> Result<Response> {
+    tracing::debug!("get_tasks: story_id = {}", story_id);
 
-    let tasks = ctx
+    let format = Format::from_headers(&headers)?;
+    let tasks: Vec<Task> = ctx
         .story_repo
         .fetch(story_id)
         .and_then(|_| ctx.task_repo.fetch_all(story_id))
         .await?;
 
-    Ok(Json(tasks))
+    let response = match format {
+        Format::Json => Json(tasks).into_response(),
+        Format::Csv => format::csv(&tasks),
+        Format::Markdown => format::markdown_checklist(&tasks),
+    };
+
+    Ok(format::vary_accept(response))
 }
 
 /// Create a new story for an owner
+#[utoipa::path(
+    post,
+    path = "/stories",
+    tag = "stories",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the create")),
+    request_body = CreateStoryBody,
+    responses(
+        (status = 201, description = "The created story", body = Story),
+        (status = 400, description = "Invalid arguments", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 409, description = "Idempotency key in use", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 422, description = "Idempotency key reused with a different body", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn create_story(
     State(ctx): State<Arc<ApiCtx>>,
     Json(body): Json<CreateStoryBody>,
 ) -> Result<impl IntoResponse> {
-    log::debug!("create_story: {:?}", body);
+    tracing::debug!("create_story: {:?}", body);
 
+    let body = body.normalize();
     body.validate()?;
 
     let owner = body.owner.unwrap_or(BACKLOG.into());
@@ -84,14 +167,122 @@ async fn create_story(
     Ok((StatusCode::CREATED, Json(story)))
 }
 
+/// Import a new story with tasks from a todo.txt or markdown checklist.
+#[utoipa::path(
+    post,
+    path = "/stories/import",
+    tag = "stories",
+    params(ImportStoryParams),
+    request_body(
+        content = String,
+        description = "A todo.txt list, or a markdown checklist",
+        content_type = "text/plain"
+    ),
+    responses(
+        (status = 201, description = "The imported story and tasks", body = Export),
+        (status = 400, description = "Invalid lines", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn import_story(
+    Query(params): Query<ImportStoryParams>,
+    headers: HeaderMap,
+    State(ctx): State<Arc<ApiCtx>>,
+    body: String,
+) -> Result<impl IntoResponse> {
+    tracing::debug!("import_story: {:?}", params);
+
+    let syntax = params
+        .format
+        .unwrap_or_else(|| Syntax::from_headers(&headers));
+    let checklist = Checklist::parse(syntax, &body)?;
+
+    let owner = params
+        .owner
+        .as_deref()
+        .map(normalize_owner)
+        .unwrap_or(BACKLOG.into());
+    let name = params
+        .name
+        .or_else(|| checklist.title.clone())
+        .or_else(|| checklist.project().map(str::to_owned))
+        .ok_or_else(|| Error::ValidationFailed {
+            fields: vec![FieldError::new(
+                "name",
+                "required",
+                "required when the list has no title or +project",
+            )],
+        })?;
+
+    let mut fields = Vec::new();
+    let story = CreateStoryBody {
+        name,
+        owner: Some(owner.clone()),
+    }
+    .normalize();
+    if let Err(errors) = story.validate() {
+        fields.extend(field_errors("", &errors));
+    }
+
+    let mut tasks = Vec::with_capacity(checklist.items.len());
+    for item in checklist.items {
+        let task = CreateTaskBody {
+            name: item.name,
+            story_id: Uuid::nil(),
+            due_at: None,
+        }
+        .normalize();
+        if let Err(errors) = task.validate() {
+            fields.extend(field_errors(&format!("lines[{}].", item.line), &errors));
+        }
+        tasks.push(ExportTask {
+            id: Uuid::nil(),
+            name: task.name,
+            status: item.status,
+            due_at: None,
+            deleted: false,
+        });
+    }
+
+    if !fields.is_empty() {
+        return Err(Error::ValidationFailed { fields });
+    }
+
+    let export = Export {
+        version: EXPORT_VERSION,
+        owner: owner.clone(),
+        stories: vec![ExportStory {
+            id: Uuid::nil(),
+            name: story.name,
+            deleted: false,
+            tasks,
+        }],
+    };
+    let export = ctx.export_repo.import(owner, export, false).await?;
+
+    Ok((StatusCode::CREATED, Json(export)))
+}
+
 /// Update a story name and/or owner.
+#[utoipa::path(
+    patch,
+    path = "/stories/{id}",
+    tag = "stories",
+    params(("id" = Uuid, Path, description = "Story id")),
+    request_body = PatchStoryBody,
+    responses(
+        (status = 200, description = "The updated story", body = Story),
+        (status = 400, description = "Invalid arguments", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 404, description = "Story not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn update_story(
     Path(id): Path<Uuid>,
     State(ctx): State<Arc<ApiCtx>>,
     Json(body): Json<PatchStoryBody>,
 ) -> Result<Json<Story>> {
-    log::debug!("update_story: {}, {:?}", id, body);
+    tracing::debug!("update_story: {}, {:?}", id, body);
 
+    let body = body.normalize();
     body.validate()?;
     let story = ctx.story_repo.fetch(id).await?;
 
@@ -102,17 +293,23 @@ async fn update_story(
 }
 
 /// Delete a story by id
-async fn delete_story(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> StatusCode {
-    log::debug!("delete_story: {}", id);
+#[utoipa::path(
+    delete,
+    path = "/stories/{id}",
+    tag = "stories",
+    params(("id" = Uuid, Path, description = "Story id")),
+    responses(
+        (status = 204, description = "The story and its tasks were deleted"),
+        (status = 404, description = "Story not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn delete_story(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> Result<StatusCode> {
+    tracing::debug!("delete_story: {}", id);
 
-    let result = ctx
-        .story_repo
+    ctx.story_repo
         .fetch(id)
         .and_then(|_| ctx.story_repo.delete(id))
-        .await;
+        .await?;
 
-    match result {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(error) => StatusCode::from(error),
-    }
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -1,17 +1,17 @@
 use crate::{
     api::{
-        dto::{CreateTaskBody, PatchTaskBody},
-        ApiCtx,
+        dto::{CreateTaskBody, Normalize, PatchTaskBody},
+        extract::{Json, Path},
+        ApiCtx, Routes,
     },
     domain::Task,
     Result,
 };
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
-    Json, Router,
 };
 use futures_util::TryFutureExt;
 use std::sync::Arc;
@@ -19,67 +19,111 @@ use uuid::Uuid;
 use validator::Validate;
 
 /// API routes for tasks
-pub fn routes() -> Router<Arc<ApiCtx>> {
-    Router::new().route("/tasks", post(create_task)).route(
+pub(super) fn routes() -> Routes {
+    Routes::new().route("/tasks", post(create_task)).route(
         "/tasks/:id",
         get(get_task).delete(delete_task).patch(update_task),
     )
 }
 
 /// Get task by id
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    responses(
+        (status = 200, description = "The task", body = Task),
+        (status = 404, description = "Task not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn get_task(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> Result<Json<Task>> {
-    log::debug!("get_task: {}", id);
+    tracing::debug!("get_task: {}", id);
     let task = ctx.task_repo.fetch(id).await?;
     Ok(Json(task))
 }
 
 /// Create a task new task
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "tasks",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the create")),
+    request_body = CreateTaskBody,
+    responses(
+        (status = 201, description = "The created task", body = Task),
+        (status = 400, description = "Invalid arguments", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 404, description = "Story not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 409, description = "Idempotency key in use", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 422, description = "Idempotency key reused with a different body", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn create_task(
     State(ctx): State<Arc<ApiCtx>>,
     Json(body): Json<CreateTaskBody>,
 ) -> Result<impl IntoResponse> {
-    log::debug!("create_task: {:?}", body);
+    tracing::debug!("create_task: {:?}", body);
 
+    let body = body.normalize();
     body.validate()?;
 
     let task = ctx
         .story_repo
         .fetch(body.story_id)
-        .and_then(|_| ctx.task_repo.create(body.story_id, body.name))
+        .and_then(|_| ctx.task_repo.create(body.story_id, body.name, body.due_at))
         .await?;
 
     Ok((StatusCode::CREATED, Json(task)))
 }
 
-/// Update a task name and/or status.
+/// Update a task name, status and/or due date.
+#[utoipa::path(
+    patch,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    request_body = PatchTaskBody,
+    responses(
+        (status = 200, description = "The updated task", body = Task),
+        (status = 400, description = "Invalid arguments", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+        (status = 404, description = "Task not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
 async fn update_task(
     Path(id): Path<Uuid>,
     State(ctx): State<Arc<ApiCtx>>,
     Json(body): Json<PatchTaskBody>,
 ) -> Result<Json<Task>> {
-    log::debug!("update_task: {}, {:?}", id, body);
+    tracing::debug!("update_task: {}, {:?}", id, body);
 
+    let body = body.normalize();
     body.validate()?;
     let task = ctx.task_repo.fetch(id).await?;
 
-    let (name, status) = body.unwrap(task);
-    let task = ctx.task_repo.update(id, name, status).await?;
+    let (name, status, due_at) = body.unwrap(task);
+    let task = ctx.task_repo.update(id, name, status, due_at).await?;
 
     Ok(Json(task))
 }
 
 /// Delete a task by id
-async fn delete_task(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> StatusCode {
-    log::debug!("delete_task: {}", id);
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = Uuid, Path, description = "Task id")),
+    responses(
+        (status = 204, description = "The task was deleted"),
+        (status = 404, description = "Task not found", content(("application/json" = ErrorDto), ("application/problem+json" = ProblemDto))),
+    )
+)]
+async fn delete_task(Path(id): Path<Uuid>, State(ctx): State<Arc<ApiCtx>>) -> Result<StatusCode> {
+    tracing::debug!("delete_task: {}", id);
 
-    let result = ctx
-        .task_repo
+    ctx.task_repo
         .fetch(id)
         .and_then(|_| ctx.task_repo.delete(id))
-        .await;
+        .await?;
 
-    match result {
-        Ok(_) => StatusCode::NO_CONTENT,
-        Err(error) => StatusCode::from(error),
-    }
+    Ok(StatusCode::NO_CONTENT)
 }
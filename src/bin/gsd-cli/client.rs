@@ -0,0 +1,256 @@
+use reqwest::{Identity, Method, RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{path::Path, process::ExitCode};
+
+/// The error types returned by the GSD API, as problem details or the legacy shape.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ErrorDto {
+    Legacy {
+        errors: Vec<String>,
+    },
+    Problem {
+        title: String,
+        detail: Option<String>,
+    },
+}
+
+impl ErrorDto {
+    /// The messages describing an error.
+    fn messages(self) -> Vec<String> {
+        match self {
+            ErrorDto::Legacy { errors } => errors,
+            ErrorDto::Problem { title, detail } => {
+                vec![detail.unwrap_or(title)]
+            }
+        }
+    }
+}
+
+/// Errors from calling the GSD API.
+#[derive(thiserror::Error, Debug)]
+pub enum CliError {
+    #[error("{}{}", .status, summary(.messages))]
+    Api {
+        status: StatusCode,
+        messages: Vec<String>,
+    },
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("invalid client identity {path}: {message}")]
+    Identity { path: String, message: String },
+}
+
+impl CliError {
+    /// Map errors to exit codes, following the sysexits convention.
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.code())
+    }
+
+    /// The numeric exit code for an error.
+    fn code(&self) -> u8 {
+        match self {
+            CliError::Api { status, .. } => match *status {
+                StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => 65,
+                StatusCode::NOT_FOUND => 66,
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => 77,
+                StatusCode::CONFLICT
+                | StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::SERVICE_UNAVAILABLE => 75,
+                status if status.is_server_error() => 70,
+                _ => 1,
+            },
+            CliError::Http(err) if err.is_connect() => 69,
+            CliError::Http(err) if err.is_timeout() => 75,
+            CliError::Http(err) if err.is_builder() => 64,
+            CliError::Http(_) => 76,
+            CliError::Identity { .. } => 78,
+        }
+    }
+}
+
+/// Read a client certificate and its private key from a PEM file.
+fn read_identity(path: &Path) -> Result<Identity, CliError> {
+    let invalid = |message: String| CliError::Identity {
+        path: path.display().to_string(),
+        message,
+    };
+    let pem = std::fs::read(path).map_err(|err| invalid(err.to_string()))?;
+    Identity::from_pem(&pem).map_err(|err| invalid(err.to_string()))
+}
+
+/// Summarize error messages for display after the status code.
+fn summary(messages: &[String]) -> String {
+    if messages.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", messages.join(", "))
+    }
+}
+
+/// A thin client for the GSD web-service API.
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+}
+
+impl Client {
+    /// Create a client for the API at a base URL, authenticating with a TLS client certificate
+    /// when given a PEM file holding it and its private key.
+    pub fn new(url: &str, url_base: &str, identity: Option<&Path>) -> Result<Self, CliError> {
+        let base = format!(
+            "{}/{}",
+            url.trim_end_matches('/'),
+            url_base.trim_matches('/')
+        );
+        let mut http = reqwest::Client::builder();
+        if let Some(path) = identity {
+            http = http.identity(read_identity(path)?);
+        }
+        Ok(Self {
+            http: http.build()?,
+            base,
+        })
+    }
+
+    /// GET a resource with optional query parameters
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, CliError> {
+        let request = self.request(Method::GET, path).query(query);
+        let response = self.send(request).await?;
+        Ok(response.json().await?)
+    }
+
+    /// POST a JSON body, returning the created resource
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, CliError> {
+        let request = self.request(Method::POST, path).json(body);
+        let response = self.send(request).await?;
+        Ok(response.json().await?)
+    }
+
+    /// PATCH a resource with a JSON body, returning the updated resource
+    pub async fn patch<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, CliError> {
+        let request = self.request(Method::PATCH, path).json(body);
+        let response = self.send(request).await?;
+        Ok(response.json().await?)
+    }
+
+    /// DELETE a resource
+    pub async fn delete(&self, path: &str) -> Result<(), CliError> {
+        self.send(self.request(Method::DELETE, path)).await?;
+        Ok(())
+    }
+
+    /// Build a request for an API path.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{}", self.base, path))
+    }
+
+    /// Send a request, mapping error responses into errors.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, CliError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let messages = match serde_json::from_str::<ErrorDto>(&body) {
+            Ok(dto) => dto.messages(),
+            Err(_) if body.is_empty() => Vec::new(),
+            Err(_) => vec![body],
+        };
+
+        Err(CliError::Api { status, messages })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn api_error(status: StatusCode) -> CliError {
+        CliError::Api {
+            status,
+            messages: vec![],
+        }
+    }
+
+    #[test]
+    fn api_errors_map_to_exit_codes() {
+        let cases = [
+            (StatusCode::BAD_REQUEST, 65),
+            (StatusCode::UNPROCESSABLE_ENTITY, 65),
+            (StatusCode::NOT_FOUND, 66),
+            (StatusCode::UNAUTHORIZED, 77),
+            (StatusCode::FORBIDDEN, 77),
+            (StatusCode::CONFLICT, 75),
+            (StatusCode::TOO_MANY_REQUESTS, 75),
+            (StatusCode::SERVICE_UNAVAILABLE, 75),
+            (StatusCode::INTERNAL_SERVER_ERROR, 70),
+            (StatusCode::BAD_GATEWAY, 70),
+            (StatusCode::METHOD_NOT_ALLOWED, 1),
+        ];
+        for (status, code) in cases {
+            assert_eq!(api_error(status).code(), code, "{}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_errors_map_to_unavailable() {
+        // Nothing is listening, so the request can't connect
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let client = Client::new(&url, "/gsd/api/v1", None).unwrap();
+        let err = client.delete("/stories/1").await.unwrap_err();
+        assert!(matches!(err, CliError::Http(_)));
+        assert_eq!(err.code(), 69);
+    }
+
+    #[tokio::test]
+    async fn bad_responses_map_to_protocol_errors() {
+        // A server that answers every request with a body that isn't JSON
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await;
+            let response = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                            content-length: 3\r\nconnection: close\r\n\r\nnot";
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = Client::new(&url, "/gsd/api/v1", None).unwrap();
+        let err = client
+            .get::<Vec<String>>("/stories", &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::Http(_)));
+        assert_eq!(err.code(), 76);
+    }
+
+    #[test]
+    fn bad_identities_map_to_config_errors() {
+        let missing = Path::new("/nonexistent/identity.pem");
+        let err = Client::new("http://localhost:8080", "/gsd/api/v1", Some(missing))
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::Identity { .. }));
+        assert_eq!(err.code(), 78);
+    }
+}
@@ -0,0 +1,177 @@
+use clap::{Parser, Subcommand};
+use gsd::domain::{Story, Task};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde_json::json;
+use std::{path::PathBuf, process::ExitCode};
+use uuid::Uuid;
+
+mod client;
+mod output;
+
+use client::{CliError, Client};
+use output::Output;
+
+/// Command-line client for the GSD web-service.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Server URL
+    #[arg(long, env = "GSD_URL", default_value = "http://localhost:8080")]
+    url: String,
+
+    /// API path prefix, matching the server's API_URL_BASE
+    #[arg(long, env = "API_URL_BASE", default_value = "/gsd/api/v1")]
+    url_base: String,
+
+    /// PEM file with a TLS client certificate and its private key, for servers that verify them
+    #[arg(long, env = "GSD_IDENTITY")]
+    identity: Option<PathBuf>,
+
+    /// Output mode
+    #[arg(short, long, value_enum, default_value = "table")]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Manage stories
+    #[command(subcommand)]
+    Stories(StoryCommand),
+    /// Manage tasks
+    #[command(subcommand)]
+    Tasks(TaskCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum StoryCommand {
+    /// List stories for an owner
+    List {
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Create a story
+    Create {
+        name: String,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Rename a story
+    Rename { id: Uuid, name: String },
+    /// Delete a story and its tasks
+    Delete { id: Uuid },
+}
+
+#[derive(Debug, Subcommand)]
+enum TaskCommand {
+    /// List tasks for a story
+    List { story_id: Uuid },
+    /// Add a task to a story
+    Add {
+        story_id: Uuid,
+        name: String,
+        /// Due date as an RFC 3339 timestamp, e.g. 2026-10-19T17:00:00+01:00
+        #[arg(long)]
+        due: Option<String>,
+    },
+    /// List an owner's incomplete tasks that are past their due date
+    Overdue { owner: String },
+    /// Mark a task complete
+    Done { id: Uuid },
+    /// Mark a task incomplete
+    Undo { id: Uuid },
+    /// Remove a task
+    Rm { id: Uuid },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let client = match Client::new(&cli.url, &cli.url_base, cli.identity.as_deref()) {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return err.exit_code();
+        }
+    };
+
+    let result = match cli.command {
+        Command::Stories(command) => stories(&client, cli.output, command).await,
+        Command::Tasks(command) => tasks(&client, cli.output, command).await,
+    };
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            err.exit_code()
+        }
+    }
+}
+
+/// Run story subcommands
+async fn stories(client: &Client, output: Output, command: StoryCommand) -> Result<(), CliError> {
+    match command {
+        StoryCommand::List { owner } => {
+            let query: Vec<(&str, &str)> = owner.iter().map(|o| ("owner", o.as_str())).collect();
+            let stories: Vec<Story> = client.get("/stories", &query).await?;
+            output.list(&stories);
+        }
+        StoryCommand::Create { name, owner } => {
+            let body = json!({ "name": name, "owner": owner });
+            let story: Story = client.post("/stories", &body).await?;
+            output.item(&story);
+        }
+        StoryCommand::Rename { id, name } => {
+            let body = json!({ "name": name });
+            let story: Story = client.patch(&format!("/stories/{}", id), &body).await?;
+            output.item(&story);
+        }
+        StoryCommand::Delete { id } => {
+            client.delete(&format!("/stories/{}", id)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Run task subcommands
+async fn tasks(client: &Client, output: Output, command: TaskCommand) -> Result<(), CliError> {
+    match command {
+        TaskCommand::List { story_id } => {
+            let path = format!("/stories/{}/tasks", story_id);
+            let tasks: Vec<Task> = client.get(&path, &[]).await?;
+            output.list(&tasks);
+        }
+        TaskCommand::Add {
+            story_id,
+            name,
+            due,
+        } => {
+            let body = json!({ "story_id": story_id, "name": name, "due_at": due });
+            let task: Task = client.post("/tasks", &body).await?;
+            output.item(&task);
+        }
+        TaskCommand::Overdue { owner } => {
+            let owner = utf8_percent_encode(&owner, NON_ALPHANUMERIC);
+            let path = format!("/owners/{}/tasks/overdue", owner);
+            let tasks: Vec<Task> = client.get(&path, &[]).await?;
+            output.list(&tasks);
+        }
+        TaskCommand::Done { id } => {
+            let body = json!({ "status": "complete" });
+            let task: Task = client.patch(&format!("/tasks/{}", id), &body).await?;
+            output.item(&task);
+        }
+        TaskCommand::Undo { id } => {
+            let body = json!({ "status": "incomplete" });
+            let task: Task = client.patch(&format!("/tasks/{}", id), &body).await?;
+            output.item(&task);
+        }
+        TaskCommand::Rm { id } => {
+            client.delete(&format!("/tasks/{}", id)).await?;
+        }
+    }
+    Ok(())
+}
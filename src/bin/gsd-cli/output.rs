@@ -0,0 +1,94 @@
+use clap::ValueEnum;
+use gsd::domain::{Story, Task};
+use serde::Serialize;
+
+/// Output modes for command results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Table,
+    Json,
+}
+
+/// Types that can be printed as table rows.
+pub trait Tabular {
+    /// Column names
+    fn header() -> Vec<&'static str>;
+    /// Column values for a single row
+    fn row(&self) -> Vec<String>;
+}
+
+impl Tabular for Story {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "NAME", "OWNER"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.owner.clone()]
+    }
+}
+
+impl Tabular for Task {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "STORY_ID", "NAME", "STATUS", "DUE"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.story_id.to_string(),
+            self.name.clone(),
+            self.status.to_string(),
+            self.due_at
+                .map(|due_at| due_at.to_rfc3339())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl Output {
+    /// Print a single item
+    pub fn item<T: Tabular + Serialize>(self, item: &T) {
+        match self {
+            Output::Table => print_table(std::slice::from_ref(item)),
+            Output::Json => print_json(item),
+        }
+    }
+
+    /// Print a list of items
+    pub fn list<T: Tabular + Serialize>(self, items: &[T]) {
+        match self {
+            Output::Table => print_table(items),
+            Output::Json => print_json(&items),
+        }
+    }
+}
+
+/// Print items as pretty JSON.
+fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(err) => eprintln!("error: {}", err),
+    }
+}
+
+/// Print items as a table with aligned columns.
+fn print_table<T: Tabular>(items: &[T]) {
+    let header: Vec<String> = T::header().into_iter().map(String::from).collect();
+    let rows: Vec<Vec<String>> = items.iter().map(Tabular::row).collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows.iter() {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
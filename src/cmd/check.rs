@@ -0,0 +1,65 @@
+use crate::{
+    cmd::CmdResult,
+    config::{Config, NotifierKind},
+    notify,
+    tls::Tls,
+};
+use std::sync::Arc;
+
+/// Print the loaded configuration and check that the database is reachable.
+pub async fn check_config(config: Arc<Config>) -> CmdResult {
+    println!("listen_addr = {}", config.listen_addr);
+    if let Some(addr) = &config.grpc_listen_addr {
+        println!("grpc_listen_addr = {}", addr);
+    }
+    if let Some(addr) = &config.admin_listen_addr {
+        println!("admin_listen_addr = {}", addr);
+    }
+    println!("url_base = {}", config.url_base);
+    // Loading checks that certificates and keys are valid
+    if Tls::load(&config)?.is_some() {
+        let client_auth = match (&config.tls_client_ca_path, config.tls_client_auth_required) {
+            (None, _) => "off",
+            (Some(_), false) => "optional",
+            (Some(_), true) => "required",
+        };
+        println!("tls = on, client auth {}", client_auth);
+    }
+    println!(
+        "shutdown = delay {}, timeout {}",
+        humantime::format_duration(config.shutdown_delay),
+        humantime::format_duration(config.shutdown_timeout)
+    );
+    // Creating the notifier checks the SMTP settings
+    notify::notifier(&config)?;
+    match (config.notifier, &config.smtp_host) {
+        (NotifierKind::Smtp, Some(host)) => println!(
+            "reminders = smtp {}:{} ({}), lead {}",
+            host,
+            config.smtp_port,
+            config.smtp_security,
+            humantime::format_duration(config.reminder_lead)
+        ),
+        (notifier, _) => println!(
+            "reminders = {}, lead {}",
+            notifier,
+            humantime::format_duration(config.reminder_lead)
+        ),
+    }
+    println!(
+        "database = {}@{}:{}/{} (schema {}, max connections {})",
+        config.db_user,
+        config.db_host,
+        config.db_port,
+        config.db_database,
+        config.db_schema,
+        config.db_max_connections
+    );
+
+    let pool = config.db_pool().await?;
+    sqlx::query("SELECT 1").execute(&pool).await?;
+    pool.close().await;
+
+    println!("Database connection OK");
+    Ok(())
+}
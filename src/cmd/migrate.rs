@@ -0,0 +1,71 @@
+use crate::{
+    api::dto::normalize_owner,
+    cmd::CmdResult,
+    config::Config,
+    repo::{AdminRepo, MigrationState, MIGRATOR},
+};
+use sqlx::postgres::PgPool;
+use std::sync::Arc;
+
+/// Apply all pending migrations.
+pub async fn migrate_up(config: Arc<Config>) -> CmdResult {
+    let pool = Arc::new(config.db_pool().await?);
+
+    run_migrations(&pool).await?;
+    pool.close().await;
+
+    println!("Migrations are up to date");
+    Ok(())
+}
+
+/// Apply pending migrations, then normalize owners once, since SQL can't case fold them the way
+/// the service does.
+pub(super) async fn run_migrations(pool: &Arc<PgPool>) -> CmdResult {
+    tracing::info!("Running migrations");
+    MIGRATOR.run(pool.as_ref()).await?;
+
+    let backfill = AdminRepo::new(Arc::clone(pool))
+        .normalize_owners(normalize_owner)
+        .await?;
+    if let Some(backfill) = backfill {
+        tracing::info!("Normalized {} owners", backfill.renamed);
+        for owner in backfill.skipped {
+            tracing::warn!(
+                "Owner {:?} is too long once normalized, leaving it unchanged",
+                owner
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the state of each embedded migration.
+pub async fn migrate_status(config: Arc<Config>) -> CmdResult {
+    let pool = Arc::new(config.db_pool().await?);
+    let admin_repo = AdminRepo::new(Arc::clone(&pool));
+
+    let status = admin_repo.migration_status().await?;
+    pool.close().await;
+
+    for migration in status.iter() {
+        let state = match migration.state {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::ChecksumMismatch => "checksum mismatch",
+        };
+        println!(
+            "{:<16} {:<32} {}",
+            migration.version, migration.description, state
+        );
+    }
+
+    if status
+        .iter()
+        .any(|m| m.state == MigrationState::ChecksumMismatch)
+    {
+        return Err("applied migrations differ from the embedded migrations".into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use std::error::Error;
+
+// Validate configuration settings
+mod check;
+
+// Database migrations
+mod migrate;
+
+// Permanently delete soft deleted data
+mod purge;
+
+// Demo data
+mod seed;
+
+// The web-service
+mod serve;
+
+pub use check::check_config;
+pub use migrate::{migrate_status, migrate_up};
+pub use purge::purge;
+pub use seed::seed;
+pub use serve::serve;
+
+/// Result type for server subcommands
+pub type CmdResult = Result<(), Box<dyn Error>>;
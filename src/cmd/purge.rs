@@ -0,0 +1,14 @@
+use crate::{cmd::CmdResult, config::Config, repo::AdminRepo};
+use std::{sync::Arc, time::Duration};
+
+/// Permanently delete stories and tasks that were soft deleted before a cutoff.
+pub async fn purge(config: Arc<Config>, older_than: Duration) -> CmdResult {
+    let pool = Arc::new(config.db_pool().await?);
+    let admin_repo = AdminRepo::new(Arc::clone(&pool));
+
+    let (stories, tasks) = admin_repo.purge(older_than).await?;
+    pool.close().await;
+
+    println!("Purged {} stories and {} tasks", stories, tasks);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::{
+    api::dto::normalize_owner,
+    cmd::CmdResult,
+    config::Config,
+    domain::Status,
+    repo::{StoryRepo, TaskRepo},
+};
+use std::sync::Arc;
+
+/// Demo stories and their tasks
+const STORIES: &[(&str, &[(&str, Status)])] = &[
+    (
+        "Books To Read",
+        &[
+            ("Suttree", Status::Complete),
+            ("Blood Meridian", Status::Incomplete),
+            ("The Road", Status::Incomplete),
+        ],
+    ),
+    (
+        "Groceries",
+        &[("Coffee", Status::Incomplete), ("Eggs", Status::Complete)],
+    ),
+];
+
+/// Create demo stories and tasks for an owner.
+pub async fn seed(config: Arc<Config>, owner: String) -> CmdResult {
+    let pool = Arc::new(config.db_pool().await?);
+    let story_repo = StoryRepo::new(Arc::clone(&pool));
+    let task_repo = TaskRepo::new(Arc::clone(&pool));
+    let owner = normalize_owner(&owner);
+
+    for (name, tasks) in STORIES {
+        let story = story_repo.create(name.to_string(), owner.clone()).await?;
+        for (name, status) in tasks.iter() {
+            let task = task_repo.create(story.id, name.to_string(), None).await?;
+            if *status != task.status {
+                task_repo
+                    .update(task.id, task.name, *status, task.due_at)
+                    .await?;
+            }
+        }
+        println!("Created story {} ({})", story.name, story.id);
+    }
+
+    pool.close().await;
+    Ok(())
+}
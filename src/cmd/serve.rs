@@ -0,0 +1,290 @@
+use crate::{
+    admin::{self, Admin},
+    api::{self, Api, ApiCtx},
+    cmd::{migrate::run_migrations, CmdResult},
+    config::Config,
+    grpc::Grpc,
+    notify::{self, Reminders},
+    repo::Replica,
+    telemetry,
+    tls::{self, Tls},
+};
+use axum::{middleware, Router};
+use chrono::Utc;
+use futures_util::future::{self, FutureExt, LocalBoxFuture};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{signal, time};
+use tonic::transport::Server;
+
+/// How often idle rate limit buckets are dropped
+const RATE_LIMIT_PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often expired idempotency keys are deleted
+const IDEMPOTENCY_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the read replica is checked
+const REPLICA_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often recurring tasks are checked for due occurrences
+const RECURRENCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Run the web-service, optionally applying pending migrations first.
+pub async fn serve(config: Arc<Config>, migrate: bool) -> CmdResult {
+    // Create pg connection pool
+    let pool = Arc::new(config.db_pool().await?);
+
+    if migrate {
+        run_migrations(&pool).await?;
+    }
+
+    // Send reads to a replica when configured, once a check finds it available
+    let replica = config
+        .db_replica_pool()?
+        .map(|pool| Arc::new(Replica::new(pool)));
+
+    // Set up API
+    let ctx = Arc::new(ApiCtx::new(
+        Arc::clone(&config),
+        Arc::clone(&pool),
+        replica.clone(),
+    ));
+    let api = Api::new(Arc::clone(&ctx));
+    let router = Router::new()
+        .nest(&config.url_base, api.routes())
+        .merge(admin::health_routes().with_state(Arc::clone(&ctx)))
+        .fallback(api::not_found)
+        // Outside the API, so errors for unknown paths are rendered in the chosen format too
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&ctx),
+            api::error_format,
+        ))
+        .layer(middleware::from_fn(admin::track_requests))
+        .layer(middleware::from_fn(telemetry::access_log))
+        .layer(middleware::from_fn(telemetry::trace_requests));
+
+    // Start server, over TLS when configured
+    let listener = config.tcp_listener()?;
+    let shutdown = ctx.shutdown_token();
+    let mut servers: Vec<LocalBoxFuture<CmdResult>> = match Tls::load(&config)? {
+        Some(tls) => {
+            tracing::info!("Server listening on {} with TLS", config.listen_addr);
+            let tls = Arc::new(tls);
+            reload_certificates(&ctx, Arc::clone(&tls));
+            vec![async move {
+                tls::serve_tls(listener, router, tls, shutdown).await?;
+                Ok(())
+            }
+            .boxed_local()]
+        }
+        None => {
+            tracing::info!("Server listening on {}", config.listen_addr);
+            vec![async move {
+                let service = router.into_make_service_with_connect_info::<SocketAddr>();
+                axum::serve(listener, service)
+                    .with_graceful_shutdown(shutdown.cancelled_owned())
+                    .await?;
+                Ok(())
+            }
+            .boxed_local()]
+        }
+    };
+
+    // Start gRPC server alongside when configured
+    if let Some(addr) = config.grpc_listen_addr {
+        tracing::info!("gRPC server listening on {}", addr);
+        let routes = Grpc::new(Arc::clone(&ctx)).routes();
+        let shutdown = ctx.shutdown_token();
+        let timeout = config.request_timeout;
+        servers.push(
+            async move {
+                Server::builder()
+                    .timeout(timeout)
+                    .trace_fn(telemetry::grpc_span)
+                    .add_routes(routes)
+                    .serve_with_shutdown(addr, shutdown.cancelled_owned())
+                    .await?;
+                Ok(())
+            }
+            .boxed_local(),
+        );
+    }
+
+    // Start admin server with metrics when configured
+    if let Some(listener) = config.admin_tcp_listener()? {
+        tracing::info!("Admin server listening on {}", listener.local_addr()?);
+        let router = Admin::new(Arc::clone(&ctx), admin::install_recorder()?).routes();
+        let shutdown = ctx.shutdown_token();
+        servers.push(
+            async move {
+                axum::serve(listener, router)
+                    .with_graceful_shutdown(shutdown.cancelled_owned())
+                    .await?;
+                Ok(())
+            }
+            .boxed_local(),
+        );
+    }
+
+    // Periodically drop rate limit buckets for idle clients
+    let rate_limiters = Arc::clone(&ctx.rate_limiters);
+    ctx.spawn_worker(|shutdown| async move {
+        let mut interval = time::interval(RATE_LIMIT_PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => rate_limiters.purge(),
+            }
+        }
+    });
+
+    // Periodically delete expired idempotency keys
+    let idempotency_repo = Arc::clone(&ctx.idempotency_repo);
+    ctx.spawn_worker(|shutdown| async move {
+        let mut interval = time::interval(IDEMPOTENCY_PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => match idempotency_repo.purge_expired().await {
+                    Ok(count) => tracing::debug!("Purged {} expired idempotency keys", count),
+                    Err(err) => tracing::warn!("Failed to purge idempotency keys: {}", err),
+                },
+            }
+        }
+    });
+
+    // Periodically check the replica, so reads fall back to the primary while it is down
+    if replica.is_some() {
+        let admin_repo = Arc::clone(&ctx.admin_repo);
+        ctx.spawn_worker(|shutdown| async move {
+            let mut interval = time::interval(REPLICA_CHECK_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => {
+                        admin_repo.ping_replica().await;
+                    }
+                }
+            }
+        });
+    }
+
+    // Periodically create the next occurrences of recurring tasks that are due
+    let recurrence_repo = Arc::clone(&ctx.recurrence_repo);
+    ctx.spawn_worker(|shutdown| async move {
+        let mut interval = time::interval(RECURRENCE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => match recurrence_repo.create_due(Utc::now()).await {
+                    Ok(tasks) if tasks.is_empty() => {}
+                    Ok(tasks) => tracing::info!("Created {} recurring tasks", tasks.len()),
+                    Err(err) => tracing::warn!("Failed to create recurring tasks: {}", err),
+                },
+            }
+        }
+    });
+
+    // Periodically remind owners of tasks approaching their due dates
+    let reminders = Reminders::new(
+        Arc::clone(&ctx.reminder_repo),
+        notify::notifier(&config)?,
+        config.reminder_lead,
+    );
+    let reminder_interval = config.reminder_interval;
+    ctx.spawn_worker(|shutdown| async move {
+        let mut interval = time::interval(reminder_interval);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => match reminders.send_due(Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Sent {} due date reminders", count),
+                    Err(err) => tracing::warn!("Failed to send due date reminders: {}", err),
+                },
+            }
+        }
+    });
+
+    // Flip readiness, then stop accepting connections on a shutdown signal.
+    tokio::spawn(shutdown_on_signal(Arc::clone(&ctx)));
+
+    // Drain in-flight requests, up to the shutdown timeout.
+    let drain_timeout = async {
+        ctx.shutdown_token().cancelled().await;
+        time::sleep(config.shutdown_timeout).await;
+    };
+    tokio::select! {
+        result = future::try_join_all(servers) => { result?; }
+        _ = drain_timeout => tracing::warn!("Timed out draining connections"),
+    }
+
+    // Stop background workers, then release database connections.
+    if time::timeout(config.shutdown_timeout, ctx.wait_for_workers())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Timed out waiting for background workers");
+    }
+    pool.close().await;
+    if let Some(replica) = replica {
+        replica.pool().close().await;
+    }
+    tracing::info!("Server stopped");
+
+    Ok(())
+}
+
+/// Poll for changed certificate files, reloading them for new connections.
+fn reload_certificates(ctx: &ApiCtx, tls: Arc<Tls>) {
+    let period = ctx.config.tls_reload_interval;
+    ctx.spawn_worker(|shutdown| async move {
+        let mut interval = time::interval(period);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => match tls.reload_if_changed() {
+                    Ok(true) => tracing::info!("Reloaded TLS certificates"),
+                    Ok(false) => {}
+                    Err(err) => tracing::error!("failed to reload TLS certificates: {}", err),
+                },
+            }
+        }
+    });
+}
+
+/// Wait for SIGTERM or SIGINT, then shut down the service.
+async fn shutdown_on_signal(ctx: Arc<ApiCtx>) {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            tracing::error!("failed to listen for ctrl-c: {}", err);
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("failed to listen for SIGTERM: {}", err);
+                future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    // Fail readiness first, giving load balancers time to stop routing new requests.
+    tracing::info!("Shutdown signal received, draining connections");
+    ctx.start_draining();
+    time::sleep(ctx.config.shutdown_delay).await;
+    ctx.shutdown();
+}
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, fmt, fs, path::Path, str::FromStr, time::Duration};
+
+/// Suffix for keys whose value is read from a file, e.g. `DB_PASS_FILE`
+const FILE_SUFFIX: &str = "_FILE";
+
+/// A single invalid or missing setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+/// Every problem found while loading config.
+#[derive(thiserror::Error, Debug)]
+pub struct ConfigError {
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.problems {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads settings from env vars layered over a TOML file, collecting problems as it goes.
+///
+/// File keys are the lowercase env var names. Nested tables are flattened with `_`, so
+/// `[db] host = "..."` sets `DB_HOST`. Any key can also be read from a file named by `<KEY>_FILE`.
+pub struct Loader {
+    env: HashMap<String, String>,
+    file: HashMap<String, String>,
+    problems: Vec<ConfigProblem>,
+}
+
+impl Loader {
+    /// Create a loader over env vars and an optional TOML file.
+    pub fn new(env: HashMap<String, String>, path: Option<&Path>) -> Self {
+        let mut loader = Self {
+            env,
+            file: HashMap::new(),
+            problems: Vec::new(),
+        };
+        if let Some(path) = path {
+            match fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| text.parse::<toml::Table>().map_err(|err| err.to_string()))
+            {
+                Ok(table) => flatten("", table, &mut loader.file),
+                Err(message) => loader.problem(&path.display().to_string(), message),
+            }
+        }
+        loader
+    }
+
+    /// Record a problem with a setting.
+    pub fn problem(&mut self, key: &str, message: impl Into<String>) {
+        self.problems.push(ConfigProblem {
+            key: key.to_owned(),
+            message: message.into(),
+        });
+    }
+
+    /// Finish loading, failing with every problem found.
+    pub fn finish<T>(self, value: T) -> Result<T, ConfigError> {
+        if self.problems.is_empty() {
+            Ok(value)
+        } else {
+            Err(ConfigError {
+                problems: self.problems,
+            })
+        }
+    }
+
+    /// Look up a raw value: env vars first, then the file, each allowing a `_FILE` indirection.
+    pub fn get(&mut self, key: &str) -> Option<String> {
+        let file_key = format!("{}{}", key, FILE_SUFFIX);
+        let value = self
+            .env
+            .get(key)
+            .cloned()
+            .map(Ok)
+            .or_else(|| self.env.get(&file_key).map(|path| read_secret(path)))
+            .or_else(|| self.file.get(key).cloned().map(Ok))
+            .or_else(|| self.file.get(&file_key).map(|path| read_secret(path)));
+
+        match value {
+            Some(Ok(value)) => Some(value),
+            Some(Err(message)) => {
+                self.problem(&file_key, message);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// A string setting with a default.
+    pub fn string(&mut self, key: &str, default: &str) -> String {
+        self.get(key).unwrap_or_else(|| default.to_owned())
+    }
+
+    /// A setting that must be present.
+    pub fn required(&mut self, key: &str) -> String {
+        self.get(key).unwrap_or_else(|| {
+            self.problem(key, "not set");
+            String::new()
+        })
+    }
+
+    /// An optional setting parsed from a string.
+    pub fn optional<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.get(key)?;
+        match value.trim().parse() {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                self.problem(key, format!("could not parse '{}': {}", value, err));
+                None
+            }
+        }
+    }
+
+    /// A setting parsed from a string, with a default.
+    pub fn parse<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.optional(key).unwrap_or(default)
+    }
+
+    /// A duration setting, e.g. `30s` or `1m`.
+    pub fn duration(&mut self, key: &str, default: Duration) -> Duration {
+        self.optional::<humantime::Duration>(key)
+            .map(Into::into)
+            .unwrap_or(default)
+    }
+
+    /// A comma separated list setting.
+    pub fn list(&mut self, key: &str, default: &str) -> Vec<String> {
+        self.string(key, default)
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// A comma separated list setting, with each item parsed from a string.
+    pub fn parse_list<T>(&mut self, key: &str, default: &str) -> Vec<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let mut parsed = Vec::new();
+        for item in self.list(key, default) {
+            match item.parse() {
+                Ok(value) => parsed.push(value),
+                Err(err) => self.problem(key, format!("could not parse '{}': {}", item, err)),
+            }
+        }
+        parsed
+    }
+}
+
+/// Read a value from a file, dropping the trailing newline.
+fn read_secret(path: &str) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|value| value.trim_end_matches(['\r', '\n']).to_owned())
+        .map_err(|err| format!("failed to read {}: {}", path, err))
+}
+
+/// Flatten a TOML table into uppercase env var style keys.
+fn flatten(prefix: &str, table: toml::Table, values: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.to_uppercase()
+        } else {
+            format!("{}_{}", prefix, key.to_uppercase())
+        };
+        match value {
+            toml::Value::Table(table) => flatten(&key, table, values),
+            toml::Value::String(s) => {
+                values.insert(key, s);
+            }
+            toml::Value::Array(items) => {
+                let items: Vec<String> = items
+                    .into_iter()
+                    .map(|item| match item {
+                        toml::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .collect();
+                values.insert(key, items.join(","));
+            }
+            other => {
+                values.insert(key, other.to_string());
+            }
+        }
+    }
+}
@@ -1,68 +1,539 @@
-use std::env;
+use crate::tls;
+use axum::http::{HeaderName, HeaderValue, Method};
+use lettre::message::Mailbox;
+use std::{
+    collections::HashMap,
+    env,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use strum_macros::{Display, EnumString};
 
 // DB related config
 mod database;
 
+// Layered config sources
+mod loader;
+
+// Request rate limits
+mod rate_limit;
+
+// Redacted config values
+mod secret;
+
 // TCP related config
 mod tcp;
 
+use database::DatabaseUrl;
+use loader::Loader;
+
+pub use loader::{ConfigError, ConfigProblem};
+pub use rate_limit::RateLimit;
+pub use secret::Secret;
+
+/// Output formats for logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Shapes for error response bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorFormat {
+    /// RFC 7807 `application/problem+json`
+    Problem,
+    /// `{"errors": [String]}`
+    #[default]
+    Legacy,
+}
+
+/// Where due date reminders are sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum NotifierKind {
+    /// Written to the service log
+    #[default]
+    Log,
+    /// Emailed to owners with an email address
+    Smtp,
+}
+
+/// How connections to the SMTP server are secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Plain text, only for local relays and testing
+    None,
+    /// Upgrade a plain connection with `STARTTLS`
+    #[default]
+    Starttls,
+    /// Implicit TLS
+    Tls,
+}
+
 /// Configuration settings
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub listen_addr: String,
+    pub listen_addr: SocketAddr,
+    pub grpc_listen_addr: Option<SocketAddr>,
+    pub admin_listen_addr: Option<SocketAddr>,
     pub db_max_connections: u32,
+    pub db_connect_timeout: Duration,
+    pub db_acquire_timeout: Duration,
     pub db_host: String,
     pub db_port: u16,
     pub db_user: String,
-    pub db_password: String,
+    pub db_password: Secret,
     pub db_database: String,
     pub db_schema: String,
+    pub db_replica_host: Option<String>,
+    pub db_replica_port: u16,
+    pub db_replica_lag: Duration,
+    pub session_secret: Option<Secret>,
     pub url_base: String,
+    pub api_docs: bool,
+    pub rate_limit_reads: Option<RateLimit>,
+    pub rate_limit_writes: Option<RateLimit>,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub cors_allowed_origins: Vec<HeaderValue>,
+    pub cors_allowed_methods: Vec<Method>,
+    pub cors_allowed_headers: Vec<HeaderName>,
+    pub max_body_size: usize,
+    pub request_timeout: Duration,
+    pub idempotency_ttl: Duration,
+    pub tls_cert_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    pub tls_client_ca_path: Option<PathBuf>,
+    pub tls_client_auth_required: bool,
+    pub tls_reload_interval: Duration,
+    pub shutdown_delay: Duration,
+    pub shutdown_timeout: Duration,
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub log_format: LogFormat,
+    pub error_format: ErrorFormat,
+    pub reminder_lead: Duration,
+    pub reminder_interval: Duration,
+    pub notifier: NotifierKind,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<Secret>,
+    pub smtp_from: Option<Mailbox>,
 }
 
-/// Default for config just calls basic constructor
-impl Default for Config {
-    fn default() -> Self {
-        Self::load()
+impl Config {
+    /// Load config from env vars layered over an optional TOML file.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        Self::from_sources(env::vars().collect(), path)
     }
-}
 
-impl Config {
-    /// Load config from env vars.
-    pub fn load() -> Self {
-        // http server settings
-        let port = env::var("HTTP_SERVER_PORT").unwrap_or("8080".into());
-        let listen_addr = format!("0.0.0.0:{}", port);
-
-        // database settings
-        let mut db_max_connections = (num_cpus::get() * 2 + 1) as u32;
-        if let Ok(s) = env::var("DB_MAX_CONNECTIONS") {
-            db_max_connections = s.parse().expect("DB_MAX_CONNECTIONS could not be parsed")
+    /// Load config from the given env vars and optional TOML file, reporting every problem found.
+    pub fn from_sources(
+        env: HashMap<String, String>,
+        path: Option<&Path>,
+    ) -> Result<Self, ConfigError> {
+        let mut l = Loader::new(env, path);
+
+        // http server settings, listening on all interfaces
+        let any = |port| SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+        let listen_addr = any(l.parse("HTTP_SERVER_PORT", 8080));
+
+        // grpc server settings
+        let grpc_listen_addr = l.optional("GRPC_SERVER_PORT").map(any);
+
+        // admin server settings
+        let admin_listen_addr = l.optional("ADMIN_SERVER_PORT").map(any);
+
+        // database settings, DB_* vars override the parts of DATABASE_URL
+        let db_url = match l.get("DATABASE_URL") {
+            Some(s) => DatabaseUrl::parse(&s).unwrap_or_else(|err| {
+                l.problem("DATABASE_URL", err);
+                DatabaseUrl::default()
+            }),
+            None => DatabaseUrl::default(),
+        };
+        let db_max_connections = l.parse("DB_MAX_CONNECTIONS", (num_cpus::get() * 2 + 1) as u32);
+        if db_max_connections == 0 {
+            l.problem("DB_MAX_CONNECTIONS", "must be greater than zero");
+        }
+        let db_connect_timeout = l.duration("DB_CONNECT_TIMEOUT", Duration::from_secs(30));
+        let db_acquire_timeout = l.duration("DB_ACQUIRE_TIMEOUT", Duration::from_secs(5));
+        let db_host = l.get("DB_HOST").or(db_url.host);
+        let db_port = l.optional("DB_PORT").or(db_url.port).unwrap_or(5432);
+        let db_user = l.get("DB_USER").or(db_url.user);
+        let db_password = l.get("DB_PASS").or(db_url.password);
+        let db_database = l.get("DB_NAME").or(db_url.database);
+        let [db_host, db_user, db_password, db_database] = [
+            ("DB_HOST", db_host),
+            ("DB_USER", db_user),
+            ("DB_PASS", db_password),
+            ("DB_NAME", db_database),
+        ]
+        .map(|(key, value)| {
+            value.unwrap_or_else(|| {
+                l.problem(key, "not set (and not given by DATABASE_URL)");
+                String::new()
+            })
+        });
+        let db_schema = l.required("DB_SCHEMA");
+        if !db_schema.is_empty() && !is_identifier(&db_schema) {
+            l.problem(
+                "DB_SCHEMA",
+                format!("'{}' is not a valid schema name", db_schema),
+            );
+        }
+
+        // read replica settings, with the same credentials and database as the primary
+        let db_replica_host = l.get("DB_REPLICA_HOST");
+        let db_replica_port = l.parse("DB_REPLICA_PORT", db_port);
+        let db_replica_lag = l.duration("DB_REPLICA_LAG", Duration::from_secs(5));
+        let session_secret = l.get("SESSION_SECRET").map(Secret::from);
+        if db_replica_host.is_some() && session_secret.is_none() {
+            l.problem("SESSION_SECRET", "required when DB_REPLICA_HOST is set");
         }
-        let db_host = env::var("DB_HOST").expect("DB_HOST not set");
-        let db_port = env::var("DB_PORT")
-            .unwrap_or("5432".to_owned())
-            .parse()
-            .expect("DB_PORT could not be parsed");
-        let db_user = env::var("DB_USER").expect("DB_USER not set");
-        let db_password = env::var("DB_PASS").expect("DB_PASS not set");
-        let db_database = env::var("DB_NAME").expect("DB_NAME not set");
-        let db_schema = env::var("DB_SCHEMA").expect("DB_SCHEMA not set");
 
         // service URL
-        let url_base = env::var("API_URL_BASE").unwrap_or("/gsd/api/v1".into());
+        let url_base = l.string("API_URL_BASE", "/gsd/api/v1");
+        if !url_base.starts_with('/') {
+            l.problem("API_URL_BASE", "must start with '/'");
+        }
+        let api_docs = l.parse("API_DOCS_UI", false);
+
+        // rate limits per client
+        let rate_limit_reads = l.optional("RATE_LIMIT_READS");
+        let rate_limit_writes = l.optional("RATE_LIMIT_WRITES");
+        let trusted_proxies = l.parse_list("TRUSTED_PROXIES", "");
+
+        // cross origin requests, off unless origins are set
+        let cors_allowed_origins = l.parse_list("CORS_ALLOWED_ORIGINS", "");
+        let cors_allowed_methods = l.parse_list("CORS_ALLOWED_METHODS", "GET,POST,PATCH,DELETE");
+        let cors_allowed_headers = l.parse_list(
+            "CORS_ALLOWED_HEADERS",
+            "content-type,idempotency-key,x-api-key,x-error-format,x-request-id,x-session-token",
+        );
+
+        // request limits
+        let max_body_size = l.parse("MAX_BODY_SIZE", 1024 * 1024);
+        let request_timeout = l.duration("REQUEST_TIMEOUT", Duration::from_secs(30));
+        let idempotency_ttl = l.duration("IDEMPOTENCY_KEY_TTL", Duration::from_secs(24 * 60 * 60));
+
+        // tls settings, off unless a certificate is set
+        let tls_cert_path = l.get("TLS_CERT_PATH").map(PathBuf::from);
+        let tls_key_path = l.get("TLS_KEY_PATH").map(PathBuf::from);
+        if tls_cert_path.is_some() != tls_key_path.is_some() {
+            l.problem("TLS_CERT_PATH", "must be set together with TLS_KEY_PATH");
+        }
+        let tls_client_ca_path = l.get("TLS_CLIENT_CA_PATH").map(PathBuf::from);
+        if let (Some(cert), Some(key)) = (&tls_cert_path, &tls_key_path) {
+            if let Err(err) = tls::read_certs(cert) {
+                l.problem("TLS_CERT_PATH", err.to_string());
+            }
+            if let Err(err) = tls::read_key(key) {
+                l.problem("TLS_KEY_PATH", err.to_string());
+            }
+            if let Some(Err(err)) = tls_client_ca_path.as_deref().map(tls::read_certs) {
+                l.problem("TLS_CLIENT_CA_PATH", err.to_string());
+            }
+        }
+        let tls_client_auth_required = l.parse("TLS_CLIENT_AUTH_REQUIRED", false);
+        let tls_reload_interval = l.duration("TLS_RELOAD_INTERVAL", Duration::from_secs(60));
+
+        // shutdown settings
+        let shutdown_delay = l.duration("SHUTDOWN_DELAY", Duration::from_secs(5));
+        let shutdown_timeout = l.duration("SHUTDOWN_TIMEOUT", Duration::from_secs(30));
+
+        // tracing settings
+        let otlp_endpoint = l.get("OTEL_EXPORTER_OTLP_ENDPOINT");
+        let service_name = l.string("OTEL_SERVICE_NAME", "gsd");
+        let log_format = l.parse("LOG_FORMAT", LogFormat::default());
+
+        // error response settings
+        let error_format = l.parse("ERROR_FORMAT", ErrorFormat::default());
+
+        // due date reminder settings
+        let reminder_lead = l.duration("REMINDER_LEAD", Duration::from_secs(60 * 60));
+        let reminder_interval = l.duration("REMINDER_INTERVAL", Duration::from_secs(60));
+        if reminder_interval.is_zero() {
+            l.problem("REMINDER_INTERVAL", "must be greater than zero");
+        }
+        let notifier = l.parse("NOTIFIER", NotifierKind::default());
+
+        // smtp settings, required when emailing reminders
+        let smtp_host = l.get("SMTP_HOST");
+        let smtp_security = l.parse("SMTP_SECURITY", SmtpSecurity::default());
+        let smtp_port = l.parse(
+            "SMTP_PORT",
+            match smtp_security {
+                SmtpSecurity::None => 25,
+                SmtpSecurity::Starttls => 587,
+                SmtpSecurity::Tls => 465,
+            },
+        );
+        let smtp_username = l.get("SMTP_USERNAME");
+        let smtp_password = l.get("SMTP_PASS").map(Secret::from);
+        if smtp_username.is_some() != smtp_password.is_some() {
+            l.problem("SMTP_USERNAME", "must be set together with SMTP_PASS");
+        }
+        let smtp_from = l.get("SMTP_FROM");
+        if notifier == NotifierKind::Smtp {
+            for (key, value) in [("SMTP_HOST", &smtp_host), ("SMTP_FROM", &smtp_from)] {
+                if value.is_none() {
+                    l.problem(key, "required when NOTIFIER is smtp");
+                }
+            }
+        }
+        let smtp_from = smtp_from.and_then(|from| {
+            from.parse::<Mailbox>()
+                .map_err(|err| l.problem("SMTP_FROM", format!("'{}' is not valid: {}", from, err)))
+                .ok()
+        });
 
         // Create config
-        Self {
+        let config = Self {
             listen_addr,
+            grpc_listen_addr,
+            admin_listen_addr,
             db_max_connections,
+            db_connect_timeout,
+            db_acquire_timeout,
             db_host,
             db_port,
             db_user,
-            db_password,
+            db_password: db_password.into(),
             db_database,
             db_schema,
+            db_replica_host,
+            db_replica_port,
+            db_replica_lag,
+            session_secret,
             url_base,
-        }
+            api_docs,
+            rate_limit_reads,
+            rate_limit_writes,
+            trusted_proxies,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            max_body_size,
+            request_timeout,
+            idempotency_ttl,
+            tls_cert_path,
+            tls_key_path,
+            tls_client_ca_path,
+            tls_client_auth_required,
+            tls_reload_interval,
+            shutdown_delay,
+            shutdown_timeout,
+            otlp_endpoint,
+            service_name,
+            log_format,
+            error_format,
+            reminder_lead,
+            reminder_interval,
+            notifier,
+            smtp_host,
+            smtp_port,
+            smtp_security,
+            smtp_username,
+            smtp_password,
+            smtp_from,
+        };
+        l.finish(config)
+    }
+}
+
+/// Whether a name can be used unquoted as a Postgres identifier.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = chars.next();
+    name.len() <= 63
+        && first.is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn file(text: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let secret = file("s3cret\n");
+        let toml = file(&format!(
+            r#"
+            http_server_port = 9000
+            request_timeout = "5s"
+            cors_allowed_origins = ["https://a.test", "https://b.test"]
+
+            [db]
+            host = "db.local"
+            user = "gsd"
+            pass_file = "{}"
+            name = "gsd"
+            schema = "gsd"
+            "#,
+            secret.path().display()
+        ));
+        let config = Config::from_sources(
+            env(&[("HTTP_SERVER_PORT", "9001"), ("DB_HOST", "db.override")]),
+            Some(toml.path()),
+        )
+        .unwrap();
+
+        assert_eq!(config.listen_addr.to_string(), "0.0.0.0:9001");
+        assert_eq!(config.db_host, "db.override");
+        assert_eq!(config.db_password.expose(), "s3cret");
+        assert_eq!(config.request_timeout, Duration::from_secs(5));
+        assert_eq!(
+            config.cors_allowed_origins,
+            vec!["https://a.test", "https://b.test"]
+        );
+        assert_eq!(config.shutdown_delay, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn database_url_with_overrides() {
+        let config = Config::from_sources(
+            env(&[
+                ("DATABASE_URL", "postgres://gsd:pw@db.local:6543/tasks"),
+                ("DB_NAME", "other"),
+                ("DB_SCHEMA", "gsd"),
+            ]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(config.db_host, "db.local");
+        assert_eq!(config.db_port, 6543);
+        assert_eq!(config.db_user, "gsd");
+        assert_eq!(config.db_password.expose(), "pw");
+        assert_eq!(config.db_database, "other");
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let err = Config::from_sources(
+            env(&[
+                ("HTTP_SERVER_PORT", "http"),
+                ("DB_HOST", "localhost"),
+                ("DB_USER", "gsd"),
+                ("DB_PASS_FILE", "/does/not/exist"),
+                ("DB_SCHEMA", "gsd'; DROP TABLE stories; --"),
+                ("DB_REPLICA_HOST", "replica.local"),
+                ("TRUSTED_PROXIES", "10.0.0.1, proxy.local"),
+                ("REQUEST_TIMEOUT", "soon"),
+                ("TLS_CERT_PATH", "cert.pem"),
+            ]),
+            None,
+        )
+        .unwrap_err();
+
+        let keys: Vec<_> = err.problems.iter().map(|p| p.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "HTTP_SERVER_PORT",
+                "DB_PASS_FILE",
+                "DB_PASS",
+                "DB_NAME",
+                "DB_SCHEMA",
+                "SESSION_SECRET",
+                "TRUSTED_PROXIES",
+                "REQUEST_TIMEOUT",
+                "TLS_CERT_PATH",
+            ]
+        );
+        assert!(err.to_string().contains("DB_NAME: not set"));
+    }
+
+    #[test]
+    fn reports_bad_cors_and_tls_files() {
+        let err = Config::from_sources(
+            env(&[
+                ("DATABASE_URL", "postgres://gsd:pw@db.local/gsd"),
+                ("DB_SCHEMA", "gsd"),
+                ("CORS_ALLOWED_ORIGINS", "https://gsd.local,bad\norigin"),
+                ("CORS_ALLOWED_METHODS", "GET,NOT A METHOD"),
+                ("CORS_ALLOWED_HEADERS", "content-type,bad header"),
+                ("TLS_CERT_PATH", "/does/not/exist/cert.pem"),
+                ("TLS_KEY_PATH", "/does/not/exist/key.pem"),
+                ("TLS_CLIENT_CA_PATH", "/does/not/exist/ca.pem"),
+            ]),
+            None,
+        )
+        .unwrap_err();
+
+        let keys: Vec<_> = err.problems.iter().map(|p| p.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "CORS_ALLOWED_ORIGINS",
+                "CORS_ALLOWED_METHODS",
+                "CORS_ALLOWED_HEADERS",
+                "TLS_CERT_PATH",
+                "TLS_KEY_PATH",
+                "TLS_CLIENT_CA_PATH",
+            ]
+        );
+        assert!(err.to_string().contains("could not parse 'bad header'"));
+    }
+
+    #[test]
+    fn smtp_notifier_settings() {
+        let vars = [
+            ("DATABASE_URL", "postgres://gsd:pw@db.local/gsd"),
+            ("DB_SCHEMA", "gsd"),
+            ("NOTIFIER", "smtp"),
+            ("SMTP_FROM", "not an address"),
+            ("SMTP_USERNAME", "gsd"),
+        ];
+        let err = Config::from_sources(env(&vars), None).unwrap_err();
+        let keys: Vec<_> = err.problems.iter().map(|p| p.key.as_str()).collect();
+        assert_eq!(keys, vec!["SMTP_USERNAME", "SMTP_HOST", "SMTP_FROM"]);
+
+        let vars = [
+            ("DATABASE_URL", "postgres://gsd:pw@db.local/gsd"),
+            ("DB_SCHEMA", "gsd"),
+            ("NOTIFIER", "smtp"),
+            ("SMTP_HOST", "localhost"),
+            ("SMTP_SECURITY", "none"),
+            ("SMTP_FROM", "GSD <gsd@example.com>"),
+        ];
+        let config = Config::from_sources(env(&vars), None).unwrap();
+        assert_eq!(config.smtp_port, 25);
+        assert_eq!(
+            config.smtp_from.unwrap().email.to_string(),
+            "gsd@example.com"
+        );
+    }
+
+    #[test]
+    fn schema_identifiers() {
+        assert!(is_identifier("gsd"));
+        assert!(is_identifier("_gsd_2"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier("2gsd"));
+        assert!(!is_identifier("gsd-api"));
+        assert!(!is_identifier("gsd'"));
+        assert!(!is_identifier(&"a".repeat(64)));
     }
 }
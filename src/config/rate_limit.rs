@@ -0,0 +1,47 @@
+use std::{str::FromStr, time::Duration};
+
+/// A request budget, written as `<requests>/<period>`, e.g. `100/1m` or `10/1s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, per) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected <requests>/<period>, got '{}'", s))?;
+        let requests: u32 = requests
+            .trim()
+            .parse()
+            .map_err(|err| format!("invalid request count '{}': {}", requests, err))?;
+        let per = humantime::parse_duration(per.trim())
+            .map_err(|err| format!("invalid period '{}': {}", per, err))?;
+        if requests == 0 || per.is_zero() {
+            return Err(format!("rate limit '{}' must be positive", s));
+        }
+        Ok(Self { requests, per })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rate_limits() {
+        assert_eq!(
+            "100/1m".parse(),
+            Ok(RateLimit {
+                requests: 100,
+                per: Duration::from_secs(60)
+            })
+        );
+        assert!("100".parse::<RateLimit>().is_err());
+        assert!("0/1s".parse::<RateLimit>().is_err());
+        assert!("ten/1s".parse::<RateLimit>().is_err());
+    }
+}
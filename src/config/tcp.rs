@@ -3,13 +3,14 @@ use std::{io, net::SocketAddr};
 use tokio::net::{TcpListener, TcpSocket};
 
 impl Config {
-    pub fn tcp_listener(&self) -> TcpListener {
-        let addr: SocketAddr = self
-            .listen_addr
-            .parse()
-            .expect("Failed to parse listen address");
+    /// Listener for the API.
+    pub fn tcp_listener(&self) -> io::Result<TcpListener> {
+        reuse_listener(self.listen_addr)
+    }
 
-        reuse_listener(addr).expect("Failed calling reuse_listener")
+    /// Listener for the admin endpoints, when an admin port is configured.
+    pub fn admin_tcp_listener(&self) -> io::Result<Option<TcpListener>> {
+        self.admin_listen_addr.map(reuse_listener).transpose()
     }
 }
 
@@ -24,16 +25,16 @@ fn reuse_listener(addr: SocketAddr) -> io::Result<TcpListener> {
     };
     #[cfg(unix)]
     {
-        log::debug!("cfg(unix): calling set_reuseport on socket");
+        tracing::debug!("cfg(unix): calling set_reuseport on socket");
         if let Err(e) = socket.set_reuseport(true) {
-            log::warn!("error setting SO_REUSEPORT: {}", e);
+            tracing::warn!("error setting SO_REUSEPORT: {}", e);
         }
     }
     if let Err(e) = socket.set_reuseaddr(true) {
-        log::warn!("error calling set_reuseaddr: {}", e);
+        tracing::warn!("error calling set_reuseaddr: {}", e);
     }
     if let Err(e) = socket.set_nodelay(true) {
-        log::warn!("error calling set_nodelay: {}", e);
+        tracing::warn!("error calling set_nodelay: {}", e);
     }
     socket.bind(addr)?;
     socket.listen(1024)
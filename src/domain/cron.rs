@@ -0,0 +1,185 @@
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use std::{fmt, str::FromStr};
+
+/// A five field cron expression: minute, hour, day of month, month and day of week.
+/// Fields accept `*`, values, ranges, lists and steps, e.g. `*/15 9-17 * * 1-5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// An invalid cron expression.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct CronError(String);
+
+impl FromStr for Cron {
+    type Err = CronError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(CronError(format!(
+                "expected 5 fields, found {}",
+                fields.len()
+            )));
+        };
+
+        // Sunday is both 0 and 7
+        let mut weekdays = parse_field("day of week", weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse_field("minute", minute, 0, 59)?,
+            hours: parse_field("hour", hour, 0, 23)?,
+            days: parse_field("day of month", day, 1, 31)?,
+            months: parse_field("month", month, 1, 12)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl Cron {
+    /// Whether the expression matches a date. When both day fields are restricted, either may
+    /// match, as in traditional cron.
+    pub fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        has(self.months, date.month())
+            && match (self.any_day, self.any_weekday) {
+                (true, true) => true,
+                (true, false) => weekday,
+                (false, true) => day,
+                (false, false) => day || weekday,
+            }
+    }
+
+    /// Times of day the expression matches, in order.
+    pub fn times(&self) -> impl Iterator<Item = NaiveTime> + '_ {
+        (0..24)
+            .filter(|hour| has(self.hours, *hour))
+            .flat_map(move |hour| {
+                (0..60)
+                    .filter(|minute| has(self.minutes, *minute))
+                    .filter_map(move |minute| NaiveTime::from_hms_opt(hour, minute, 0))
+            })
+    }
+}
+
+/// Whether a value is set in a field bitmask.
+fn has(field: u64, value: u32) -> bool {
+    field & (1 << value) != 0
+}
+
+/// Parse a comma separated list of values, ranges and steps into a bitmask.
+fn parse_field(name: &str, field: &str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid = |reason: &str| CronError(format!("{}: {} in `{}`", name, reason, field));
+    let value = |value: &str| {
+        value
+            .parse::<u32>()
+            .ok()
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(|| invalid(&format!("expected {} to {}", min, max)))
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| invalid("invalid step"))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // A single value with a step runs to the end of the range, e.g. `5/15`
+            None if step.is_some() => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if start > end {
+            return Err(invalid("range start after end"));
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parse_expressions() {
+        let cron: Cron = "*/20  9-10 * * 1-5".parse().unwrap();
+        assert_eq!(cron.to_string(), "*/20 9-10 * * 1-5");
+        let times: Vec<String> = cron
+            .times()
+            .map(|t| t.format("%H:%M").to_string())
+            .collect();
+        assert_eq!(
+            times,
+            ["09:00", "09:20", "09:40", "10:00", "10:20", "10:40"]
+        );
+        // 2026-10-19 is a Monday
+        assert!(cron.matches_date(date(2026, 10, 19)));
+        assert!(!cron.matches_date(date(2026, 10, 18)));
+
+        let sundays: Cron = "0 0 * * 7".parse().unwrap();
+        assert!(sundays.matches_date(date(2026, 10, 18)));
+
+        let errors = [
+            ("* * * *", "expected 5 fields, found 4"),
+            ("60 * * * *", "minute: expected 0 to 59 in `60`"),
+            ("* * * * mon", "day of week: expected 0 to 7 in `mon`"),
+            ("*/0 * * * *", "minute: invalid step in `*/0`"),
+            ("* 5-1 * * *", "hour: range start after end in `5-1`"),
+        ];
+        for (expression, message) in errors {
+            let err = expression.parse::<Cron>().unwrap_err();
+            assert_eq!(err.to_string(), message, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn match_either_day_field() {
+        // The 13th, or any Friday
+        let cron: Cron = "0 0 13 * 5".parse().unwrap();
+        assert!(cron.matches_date(date(2026, 10, 13)));
+        assert!(cron.matches_date(date(2026, 10, 16)));
+        assert!(!cron.matches_date(date(2026, 10, 14)));
+
+        let cron: Cron = "0 0 1 1,7 *".parse().unwrap();
+        assert!(cron.matches_date(date(2026, 7, 1)));
+        assert!(!cron.matches_date(date(2026, 8, 1)));
+    }
+}
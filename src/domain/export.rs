@@ -0,0 +1,39 @@
+use crate::domain::Status;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The current version of the export document format.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// A versioned document holding all stories and tasks for an owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Export {
+    pub version: u32,
+    pub owner: String,
+    pub stories: Vec<ExportStory>,
+}
+
+/// A story and its tasks within an export document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExportStory {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub tasks: Vec<ExportTask>,
+}
+
+/// A task within an export document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExportTask {
+    pub id: Uuid,
+    pub name: String,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub deleted: bool,
+}
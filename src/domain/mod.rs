@@ -1,7 +1,17 @@
+mod cron;
+mod export;
+mod owner;
+mod recurrence;
+mod reminder;
 mod status;
 mod story;
 mod task;
 
+pub use cron::{Cron, CronError};
+pub use export::{Export, ExportStory, ExportTask, EXPORT_VERSION};
+pub use owner::OwnerSettings;
+pub use recurrence::{Recurrence, TaskRecurrence, Weekday};
+pub use reminder::Reminder;
 pub use status::Status;
 pub use story::Story;
 pub use task::Task;
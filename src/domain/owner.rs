@@ -0,0 +1,25 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Settings for a story owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OwnerSettings {
+    pub owner: String,
+    /// IANA time zone used for recurrence rules and reminders
+    #[schema(value_type = String, example = "Europe/Dublin")]
+    pub timezone: Tz,
+    /// Where reminders are emailed, when sending email
+    pub email: Option<String>,
+}
+
+impl OwnerSettings {
+    /// Settings for an owner that hasn't saved any.
+    pub fn new(owner: String) -> Self {
+        Self {
+            owner,
+            timezone: Tz::UTC,
+            email: None,
+        }
+    }
+}
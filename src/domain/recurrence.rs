@@ -0,0 +1,393 @@
+use crate::{domain::Cron, error::FieldError, Error, Result};
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use strum_macros::{Display, EnumString};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Longest cron expression accepted
+const MAX_EXPRESSION_LEN: usize = 100;
+
+/// Most weekdays accepted for weekly rules, one for each day of the week
+const MAX_WEEKDAYS: usize = 7;
+
+/// How far ahead to look for the next occurrence, enough to find a leap day.
+const MAX_DAYS_AHEAD: u64 = 366 * 8;
+
+/// Days of the week for weekly rules
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    EnumString,
+    Display,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Mon => Weekday::Mon,
+            chrono::Weekday::Tue => Weekday::Tue,
+            chrono::Weekday::Wed => Weekday::Wed,
+            chrono::Weekday::Thu => Weekday::Thu,
+            chrono::Weekday::Fri => Weekday::Fri,
+            chrono::Weekday::Sat => Weekday::Sat,
+            chrono::Weekday::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// A rule for when a task recurs. Daily, weekly and monthly rules occur at midnight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "frequency", rename_all = "snake_case")]
+pub enum Recurrence {
+    /// Every day
+    Daily,
+    /// On the given days of the week
+    Weekly { weekdays: Vec<Weekday> },
+    /// On a day of the month, or the last day of shorter months
+    Monthly { day: u32 },
+    /// At times matching a five field cron expression, e.g. `0 9 * * 1-5`
+    Cron { expression: String },
+}
+
+impl Recurrence {
+    /// Check the rule, reporting invalid fields.
+    pub fn validate(&self) -> Result<()> {
+        let error = match self {
+            Recurrence::Daily => None,
+            Recurrence::Weekly { weekdays } if weekdays.is_empty() => Some(
+                FieldError::new("weekdays", "length", "at least one weekday required")
+                    .with_param("min", 1),
+            ),
+            Recurrence::Weekly { weekdays } if weekdays.len() > MAX_WEEKDAYS => Some(
+                FieldError::new("weekdays", "length", "invalid length")
+                    .with_param("min", 1)
+                    .with_param("max", MAX_WEEKDAYS),
+            ),
+            Recurrence::Weekly { .. } => None,
+            Recurrence::Monthly { day } if !(1..=31).contains(day) => Some(
+                FieldError::new("day", "range", "invalid day of month")
+                    .with_param("min", 1)
+                    .with_param("max", 31),
+            ),
+            Recurrence::Monthly { .. } => None,
+            Recurrence::Cron { expression } if expression.len() > MAX_EXPRESSION_LEN => Some(
+                FieldError::new("expression", "length", "invalid length")
+                    .with_param("max", MAX_EXPRESSION_LEN),
+            ),
+            Recurrence::Cron { expression } => match Cron::from_str(expression) {
+                Ok(_) if self.next_after(&Utc::now()).is_none() => Some(FieldError::new(
+                    "expression",
+                    "cron",
+                    "expression never matches a date",
+                )),
+                Ok(_) => None,
+                Err(err) => Some(FieldError::new("expression", "cron", err.to_string())),
+            },
+        };
+        match error {
+            Some(error) => Err(Error::ValidationFailed {
+                fields: vec![error],
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Drop repeated weekdays from weekly rules, keeping the first of each.
+    pub fn normalize(self) -> Self {
+        match self {
+            Recurrence::Weekly { weekdays } => {
+                let mut unique = Vec::with_capacity(weekdays.len());
+                for weekday in weekdays {
+                    if !unique.contains(&weekday) {
+                        unique.push(weekday);
+                    }
+                }
+                Recurrence::Weekly { weekdays: unique }
+            }
+            rule => rule,
+        }
+    }
+
+    /// The first time the rule occurs strictly after the given time, in that time's zone.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let cron = match self {
+            Recurrence::Cron { expression } => Some(Cron::from_str(expression).ok()?),
+            _ => None,
+        };
+        let times: Vec<NaiveTime> = match &cron {
+            Some(cron) => cron.times().collect(),
+            None => vec![NaiveTime::MIN],
+        };
+
+        let mut date = after.date_naive();
+        for _ in 0..MAX_DAYS_AHEAD {
+            if self.matches_date(cron.as_ref(), date) {
+                // Skip times that don't exist in the zone, such as during a DST change
+                let next = times
+                    .iter()
+                    .filter_map(|time| tz.from_local_datetime(&date.and_time(*time)).earliest())
+                    .find(|time| time > after);
+                if next.is_some() {
+                    return next;
+                }
+            }
+            date = date.checked_add_days(Days::new(1))?;
+        }
+        None
+    }
+
+    /// When the occurrence after a scheduled one is due. It is never in the past, so occurrences
+    /// missed while the scheduler wasn't running are skipped rather than created in a burst.
+    pub fn following<Tz: TimeZone>(
+        &self,
+        scheduled: &DateTime<Tz>,
+        now: &DateTime<Tz>,
+    ) -> Option<DateTime<Tz>> {
+        self.next_after(scheduled.max(now))
+    }
+
+    /// Whether the rule occurs on a date.
+    fn matches_date(&self, cron: Option<&Cron>, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Daily => true,
+            Recurrence::Weekly { weekdays } => weekdays.contains(&date.weekday().into()),
+            Recurrence::Monthly { day } => date.day() == (*day).min(days_in_month(date)),
+            Recurrence::Cron { .. } => cron.is_some_and(|cron| cron.matches_date(date)),
+        }
+    }
+}
+
+/// Rules are stored as text, e.g. `weekly mon,thu` or `cron 0 9 * * 1-5`.
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly { weekdays } => {
+                let weekdays: Vec<String> = weekdays.iter().map(Weekday::to_string).collect();
+                write!(f, "weekly {}", weekdays.join(","))
+            }
+            Recurrence::Monthly { day } => write!(f, "monthly {}", day),
+            Recurrence::Cron { expression } => write!(f, "cron {}", expression),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let invalid = || Error::ValidationFailed {
+            fields: vec![FieldError::new(
+                "rule",
+                "recurrence",
+                format!("invalid recurrence rule: {}", rule),
+            )],
+        };
+        let (frequency, args) = rule.split_once(' ').unwrap_or((rule, ""));
+        match frequency {
+            "daily" => Ok(Recurrence::Daily),
+            "weekly" => {
+                let weekdays = args
+                    .split(',')
+                    .map(Weekday::from_str)
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(|_| invalid())?;
+                Ok(Recurrence::Weekly { weekdays })
+            }
+            "monthly" => {
+                let day = args.parse().map_err(|_| invalid())?;
+                Ok(Recurrence::Monthly { day })
+            }
+            "cron" => Ok(Recurrence::Cron {
+                expression: args.to_owned(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Number of days in a date's month.
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    first
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+/// The recurrence rule for the latest occurrence of a recurring task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TaskRecurrence {
+    /// The latest occurrence
+    pub task_id: Uuid,
+    pub rule: Recurrence,
+    /// When the next occurrence will be created
+    pub next_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rules_round_trip_as_text() {
+        let rules = [
+            Recurrence::Daily,
+            Recurrence::Weekly {
+                weekdays: vec![Weekday::Mon, Weekday::Thu],
+            },
+            Recurrence::Monthly { day: 31 },
+            Recurrence::Cron {
+                expression: "0 9 * * 1-5".into(),
+            },
+        ];
+        for rule in rules {
+            assert_eq!(rule.to_string().parse::<Recurrence>().unwrap(), rule);
+        }
+        assert_eq!(Recurrence::Monthly { day: 31 }.to_string(), "monthly 31");
+        assert!("yearly".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn next_occurrences() {
+        // Sunday
+        let after = utc("2026-10-18T10:00:00Z");
+        assert_eq!(
+            Recurrence::Daily.next_after(&after),
+            Some(utc("2026-10-19T00:00:00Z"))
+        );
+        let weekly = Recurrence::Weekly {
+            weekdays: vec![Weekday::Wed, Weekday::Mon],
+        };
+        assert_eq!(weekly.next_after(&after), Some(utc("2026-10-19T00:00:00Z")));
+        assert_eq!(
+            weekly.next_after(&utc("2026-10-19T00:00:00Z")),
+            Some(utc("2026-10-21T00:00:00Z"))
+        );
+        // The last day of shorter months
+        let monthly = Recurrence::Monthly { day: 31 };
+        assert_eq!(
+            monthly.next_after(&after),
+            Some(utc("2026-10-31T00:00:00Z"))
+        );
+        assert_eq!(
+            monthly.next_after(&utc("2026-10-31T00:00:00Z")),
+            Some(utc("2026-11-30T00:00:00Z"))
+        );
+        let cron = Recurrence::Cron {
+            expression: "30 9 * * 1-5".into(),
+        };
+        assert_eq!(cron.next_after(&after), Some(utc("2026-10-19T09:30:00Z")));
+        let leap = Recurrence::Cron {
+            expression: "0 0 29 2 *".into(),
+        };
+        assert_eq!(leap.next_after(&after), Some(utc("2028-02-29T00:00:00Z")));
+    }
+
+    #[test]
+    fn following_occurrences_are_never_in_the_past() {
+        let daily = Recurrence::Daily;
+        let scheduled = utc("2026-10-20T00:00:00Z");
+        // Completed early, so the next occurrence takes the scheduled one's place
+        let now = utc("2026-10-18T10:00:00Z");
+        assert_eq!(
+            daily.following(&scheduled, &now),
+            Some(utc("2026-10-21T00:00:00Z"))
+        );
+        // Missed occurrences are skipped
+        let now = utc("2026-10-25T10:00:00Z");
+        assert_eq!(
+            daily.following(&scheduled, &now),
+            Some(utc("2026-10-26T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn validate_rules() {
+        assert!(Recurrence::Daily.validate().is_ok());
+        let invalid = [
+            (Recurrence::Weekly { weekdays: vec![] }, "weekdays"),
+            (
+                Recurrence::Weekly {
+                    weekdays: vec![Weekday::Mon; MAX_WEEKDAYS + 1],
+                },
+                "weekdays",
+            ),
+            (Recurrence::Monthly { day: 32 }, "day"),
+            (
+                Recurrence::Cron {
+                    expression: "* *".into(),
+                },
+                "expression",
+            ),
+            (
+                Recurrence::Cron {
+                    expression: "0 0 31 2 *".into(),
+                },
+                "expression",
+            ),
+        ];
+        for (rule, field) in invalid {
+            match rule.validate() {
+                Err(Error::ValidationFailed { fields }) => assert_eq!(fields[0].field, field),
+                other => panic!("expected validation error for {}: {:?}", rule, other),
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_weekdays_are_dropped() {
+        let rule = Recurrence::Weekly {
+            weekdays: vec![Weekday::Thu, Weekday::Mon, Weekday::Thu, Weekday::Mon],
+        };
+        assert!(rule.validate().is_ok());
+        assert_eq!(
+            rule.normalize(),
+            Recurrence::Weekly {
+                weekdays: vec![Weekday::Thu, Weekday::Mon],
+            }
+        );
+        assert_eq!(Recurrence::Daily.normalize(), Recurrence::Daily);
+    }
+
+    #[test]
+    fn invalid_rules_are_field_errors() {
+        for rule in ["hourly", "weekly mon,someday", "monthly last"] {
+            match rule.parse::<Recurrence>() {
+                Err(Error::ValidationFailed { fields }) => {
+                    assert_eq!(
+                        (fields[0].field.as_str(), fields[0].code.as_str()),
+                        ("rule", "recurrence")
+                    )
+                }
+                other => panic!("expected validation error for {}: {:?}", rule, other),
+            }
+        }
+    }
+}
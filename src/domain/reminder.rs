@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+/// A reminder that a task is approaching its due date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub task_id: Uuid,
+    pub task_name: String,
+    pub story_id: Uuid,
+    pub story_name: String,
+    pub owner: String,
+    pub email: Option<String>,
+    pub timezone: Tz,
+    pub due_at: DateTime<Utc>,
+}
+
+impl Reminder {
+    /// When the task is due, in the owner's time zone.
+    pub fn due_local(&self) -> DateTime<Tz> {
+        self.due_at.with_timezone(&self.timezone)
+    }
+
+    /// A one line summary, e.g. for an email subject.
+    pub fn subject(&self) -> String {
+        format!("Reminder: {} is due {}", self.task_name, self.due_text())
+    }
+
+    /// A plain text description of the reminder.
+    pub fn body(&self) -> String {
+        format!(
+            "\"{}\" in \"{}\" is due {}.\n\nTask: {}\nStory: {}\n",
+            self.task_name,
+            self.story_name,
+            self.due_text(),
+            self.task_id,
+            self.story_id
+        )
+    }
+
+    /// The due date as the owner would read it, e.g. `Mon 19 Oct 2026 09:30 IST`.
+    fn due_text(&self) -> String {
+        self.due_local().format("%a %-d %b %Y %H:%M %Z").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_in_owner_time_zone() {
+        let reminder = Reminder {
+            task_id: Uuid::nil(),
+            task_name: "File taxes".into(),
+            story_id: Uuid::nil(),
+            story_name: "Chores".into(),
+            owner: "alice".into(),
+            email: None,
+            timezone: Tz::America__New_York,
+            due_at: "2026-10-19T13:30:00Z".parse().unwrap(),
+        };
+        assert_eq!(
+            reminder.subject(),
+            "Reminder: File taxes is due Mon 19 Oct 2026 09:30 EDT"
+        );
+
+        // Standard time, after the clocks change
+        let reminder = Reminder {
+            due_at: "2026-11-02T14:30:00Z".parse().unwrap(),
+            ..reminder
+        };
+        assert_eq!(
+            reminder.due_local().to_rfc3339(),
+            "2026-11-02T09:30:00-05:00"
+        );
+        assert!(reminder
+            .body()
+            .starts_with("\"File taxes\" in \"Chores\" is due Mon 2 Nov"));
+    }
+}
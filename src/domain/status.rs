@@ -1,7 +1,24 @@
-use serde::Serialize;
+use async_graphql::Enum;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
+use utoipa::ToSchema;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, Display, Serialize)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumString,
+    Display,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    Enum,
+)]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
@@ -1,11 +1,28 @@
 use crate::domain::Status;
-use serde::Serialize;
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    SimpleObject,
+)]
 pub struct Task {
     pub id: Uuid,
     pub story_id: Uuid,
     pub name: String,
     pub status: Status,
+    /// When the task should be complete by
+    pub due_at: Option<DateTime<Utc>>,
 }
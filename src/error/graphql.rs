@@ -0,0 +1,51 @@
+use super::{validate::summarize, Error, INTERNAL_MESSAGE, SERIALIZATION_MESSAGE};
+use async_graphql::ErrorExtensions;
+
+/// Map error into a graphql error, with a machine readable code and messages as extensions.
+impl ErrorExtensions for Error {
+    fn extend(&self) -> async_graphql::Error {
+        self.record();
+        let (code, messages) = match self {
+            Error::InvalidArgs { messages } => ("invalid_args", messages.to_owned()),
+            Error::ValidationFailed { fields } => ("validation_failed", summarize(fields)),
+            Error::NotFound { message } => ("not_found", vec![message.to_owned()]),
+            Error::RateLimited { .. } => ("rate_limited", vec![self.to_string()]),
+            Error::PayloadTooLarge { .. } => ("payload_too_large", vec![self.to_string()]),
+            Error::RequestTimeout | Error::Timeout { .. } => ("timeout", vec![self.to_string()]),
+            Error::Conflict { .. } => ("conflict", vec![self.to_string()]),
+            Error::Unprocessable { .. } => ("unprocessable", vec![self.to_string()]),
+            Error::IdempotencyKeyReused => ("idempotency_key_reused", vec![self.to_string()]),
+            Error::SerializationFailure { message } => {
+                tracing::warn!("serialization failure: {}", message);
+                ("serialization_failure", vec![SERIALIZATION_MESSAGE.into()])
+            }
+            Error::MethodNotAllowed { .. } => ("method_not_allowed", vec![self.to_string()]),
+            Error::UnsupportedMediaType { message } => {
+                ("unsupported_media_type", vec![message.to_owned()])
+            }
+            Error::NotAcceptable { message } => ("not_acceptable", vec![message.to_owned()]),
+            Error::Unavailable { message } => {
+                tracing::warn!("service unavailable: {}", message);
+                (
+                    "unavailable",
+                    vec!["service unavailable, try again later".into()],
+                )
+            }
+            Error::Internal { message } => {
+                tracing::error!("internal error: {}", message);
+                ("internal", vec![INTERNAL_MESSAGE.into()])
+            }
+        };
+        // Details of these are only logged
+        let message = match self {
+            Error::Unavailable { .. }
+            | Error::SerializationFailure { .. }
+            | Error::Internal { .. } => messages.join("; "),
+            _ => self.to_string(),
+        };
+        async_graphql::Error::new(message).extend_with(|_, e| {
+            e.set("code", code);
+            e.set("messages", messages);
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use super::{validate::summarize, Error, INTERNAL_MESSAGE, SERIALIZATION_MESSAGE};
+use tonic::{Code, Status};
+
+/// Map error into a gRPC status
+impl From<Error> for Status {
+    fn from(err: Error) -> Self {
+        err.record();
+        match err {
+            Error::InvalidArgs { messages } => Status::invalid_argument(messages.join("; ")),
+            Error::ValidationFailed { fields } => {
+                Status::invalid_argument(summarize(&fields).join("; "))
+            }
+            Error::NotFound { message } => Status::not_found(message),
+            Error::RateLimited { .. } | Error::PayloadTooLarge { .. } => {
+                Status::resource_exhausted(err.to_string())
+            }
+            Error::RequestTimeout | Error::Timeout { .. } => {
+                Status::deadline_exceeded(err.to_string())
+            }
+            Error::Conflict { message } => Status::already_exists(message),
+            Error::SerializationFailure { message } => {
+                tracing::warn!("serialization failure: {}", message);
+                Status::aborted(SERIALIZATION_MESSAGE)
+            }
+            Error::Unprocessable { message } => Status::failed_precondition(message),
+            Error::IdempotencyKeyReused => Status::failed_precondition(err.to_string()),
+            Error::MethodNotAllowed { .. } => Status::unimplemented(err.to_string()),
+            Error::UnsupportedMediaType { message } | Error::NotAcceptable { message } => {
+                Status::invalid_argument(message)
+            }
+            Error::Unavailable { message } => {
+                tracing::warn!("service unavailable: {}", message);
+                Status::unavailable("service unavailable, try again later")
+            }
+            Error::Internal { message } => {
+                tracing::error!("internal error: {}", message);
+                Status::new(Code::Internal, INTERNAL_MESSAGE)
+            }
+        }
+    }
+}
@@ -1,32 +1,89 @@
-use super::Error;
+use super::{
+    retry_after_secs, validate::summarize, Error, FieldError, INTERNAL_MESSAGE,
+    SERIALIZATION_MESSAGE,
+};
+use crate::{config::ErrorFormat, telemetry};
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
+use std::future::Future;
+use utoipa::ToSchema;
+
+/// Problem details media type
+const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+tokio::task_local! {
+    static ERROR_FORMAT: ErrorFormat;
+}
+
+/// Run a future with errors rendered in the given format.
+pub async fn with_error_format<F: Future>(format: ErrorFormat, f: F) -> F::Output {
+    ERROR_FORMAT.scope(format, f).await
+}
 
-/// The type sent as an error response to the client.
-#[derive(Debug, Serialize)]
-struct ErrorDto {
+/// The legacy type sent as an error response to the client.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorDto {
     errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+/// The type sent as an RFC 7807 problem details error response to the client.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDto {
+    /// URI identifying the problem type
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// Stable error code, e.g. `validation_failed`
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    /// Invalid fields, for validation errors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
 }
 
 /// Map error into a http response
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
+        self.record();
         let status = http_status_code(&self);
-        let error = http_error_dto(&self);
-        (status, Json(error)).into_response()
+        let format = ERROR_FORMAT.try_with(|f| *f).unwrap_or_default();
+        let mut response = match format {
+            ErrorFormat::Problem => {
+                let mut response = (status, Json(problem_dto(&self, status))).into_response();
+                response.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+                );
+                response
+            }
+            ErrorFormat::Legacy => (status, Json(http_error_dto(&self))).into_response(),
+        };
+        if let Error::RateLimited { retry_after } = &self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after_secs(retry_after).into());
+        }
+        response
     }
 }
 
 /// Map error types for handlers that only return status codes.
 impl From<Error> for StatusCode {
     fn from(err: Error) -> Self {
+        err.record();
         let status = http_status_code(&err);
         if status == StatusCode::INTERNAL_SERVER_ERROR {
-            log::error!("internal error: {}", err);
+            tracing::error!("internal error: {}", err);
         }
         status
     }
@@ -35,20 +92,169 @@ impl From<Error> for StatusCode {
 fn http_status_code(err: &Error) -> StatusCode {
     match err {
         Error::NotFound { .. } => StatusCode::NOT_FOUND,
-        Error::InvalidArgs { .. } => StatusCode::BAD_REQUEST,
+        Error::InvalidArgs { .. } | Error::ValidationFailed { .. } => StatusCode::BAD_REQUEST,
         Error::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        Error::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        Error::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
+        Error::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+        Error::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        Error::Conflict { .. } | Error::SerializationFailure { .. } => StatusCode::CONFLICT,
+        Error::Unprocessable { .. } | Error::IdempotencyKeyReused => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        Error::MethodNotAllowed { .. } => StatusCode::METHOD_NOT_ALLOWED,
+        Error::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        Error::NotAcceptable { .. } => StatusCode::NOT_ACCEPTABLE,
     }
 }
 
-/// Get response type for an error.
-fn http_error_dto(err: &Error) -> ErrorDto {
-    let errors = match err {
+/// Get a short, human readable summary of an error type.
+fn title(err: &Error) -> &'static str {
+    match err {
+        Error::InvalidArgs { .. } => "Invalid arguments",
+        Error::ValidationFailed { .. } => "Validation failed",
+        Error::Internal { .. } => "Internal error",
+        Error::NotFound { .. } => "Not found",
+        Error::RateLimited { .. } => "Rate limit exceeded",
+        Error::PayloadTooLarge { .. } => "Payload too large",
+        Error::RequestTimeout => "Request timeout",
+        Error::Timeout { .. } => "Timeout",
+        Error::Unavailable { .. } => "Service unavailable",
+        Error::Conflict { .. } => "Conflict",
+        Error::Unprocessable { .. } => "Unprocessable request",
+        Error::IdempotencyKeyReused => "Idempotency key reused",
+        Error::SerializationFailure { .. } => "Serialization failure",
+        Error::MethodNotAllowed { .. } => "Method not allowed",
+        Error::UnsupportedMediaType { .. } => "Unsupported media type",
+        Error::NotAcceptable { .. } => "Not acceptable",
+    }
+}
+
+/// Get the messages describing an error.
+fn messages(err: &Error) -> Vec<String> {
+    match err {
         Error::InvalidArgs { messages } => messages.to_owned(),
+        Error::ValidationFailed { fields } => summarize(fields),
         Error::NotFound { message } => vec![message.to_owned()],
-        Error::Internal { message } => {
-            log::error!("internal error: {}", message);
+        Error::RateLimited { .. }
+        | Error::PayloadTooLarge { .. }
+        | Error::RequestTimeout
+        | Error::Timeout { .. }
+        | Error::Conflict { .. }
+        | Error::Unprocessable { .. }
+        | Error::IdempotencyKeyReused
+        | Error::MethodNotAllowed { .. } => vec![err.to_string()],
+        Error::UnsupportedMediaType { message } | Error::NotAcceptable { message } => {
             vec![message.to_owned()]
         }
-    };
-    ErrorDto { errors }
+        Error::Unavailable { message } => {
+            tracing::warn!("service unavailable: {}", message);
+            vec!["service unavailable, try again later".into()]
+        }
+        Error::SerializationFailure { message } => {
+            tracing::warn!("serialization failure: {}", message);
+            vec![SERIALIZATION_MESSAGE.into()]
+        }
+        Error::Internal { message } => {
+            tracing::error!("internal error: {}", message);
+            vec![INTERNAL_MESSAGE.into()]
+        }
+    }
+}
+
+/// Get legacy response type for an error.
+fn http_error_dto(err: &Error) -> ErrorDto {
+    ErrorDto {
+        errors: messages(err),
+        request_id: telemetry::request_id(),
+    }
+}
+
+/// Get problem details response type for an error.
+fn problem_dto(err: &Error, status: StatusCode) -> ProblemDto {
+    let code: &'static str = err.into();
+    let messages = messages(err);
+    ProblemDto {
+        problem_type: format!("urn:gsd:problem:{}", code),
+        title: title(err),
+        status: status.as_u16(),
+        detail: (!messages.is_empty()).then(|| messages.join("; ")),
+        code,
+        request_id: telemetry::request_id(),
+        errors: match err {
+            Error::ValidationFailed { fields } => fields.to_owned(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body;
+    use serde_json::{json, Value};
+
+    async fn render(format: ErrorFormat, err: Error) -> (Response, Value) {
+        let response = with_error_format(format, async { err.into_response() }).await;
+        let (parts, body) = response.into_parts();
+        let bytes = body::to_bytes(body, usize::MAX).await.unwrap();
+        let response = Response::from_parts(parts, body::Body::empty());
+        (response, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn problem_details() {
+        let err = Error::ValidationFailed {
+            fields: vec![FieldError::new("name", "length", "invalid length").with_param("max", 100)],
+        };
+        let (response, body) = render(ErrorFormat::Problem, err).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            APPLICATION_PROBLEM_JSON
+        );
+        assert_eq!(
+            body,
+            json!({
+                "type": "urn:gsd:problem:validation_failed",
+                "title": "Validation failed",
+                "status": 400,
+                "detail": "name: invalid length",
+                "code": "validation_failed",
+                "errors": [
+                    {"field": "name", "code": "length", "message": "invalid length", "params": {"max": 100}}
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn database_details_are_not_sent() {
+        let err = Error::Internal {
+            message: "error returned from database: relation \"tasks\" does not exist".into(),
+        };
+        let (_, body) = render(ErrorFormat::Problem, err).await;
+        assert_eq!(body["detail"], INTERNAL_MESSAGE);
+
+        let err = Error::SerializationFailure {
+            message: "could not serialize access due to concurrent update".into(),
+        };
+        let (response, body) = render(ErrorFormat::Legacy, err).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(body, json!({"errors": [SERIALIZATION_MESSAGE]}));
+    }
+
+    #[tokio::test]
+    async fn legacy_errors() {
+        let err = Error::NotFound {
+            message: "story not found".into(),
+        };
+        let (response, body) = render(ErrorFormat::Legacy, err).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body, json!({"errors": ["story not found"]}));
+    }
 }
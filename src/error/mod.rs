@@ -1,4 +1,12 @@
 use serde::Serialize;
+use std::time::Duration;
+use strum_macros::IntoStaticStr;
+
+// GraphQL support for errors
+mod graphql;
+
+// gRPC support for errors
+mod grpc;
 
 // Http support for errors
 mod http;
@@ -6,14 +14,63 @@ mod http;
 // Validation support for errors
 mod validate;
 
+pub use http::{with_error_format, ErrorDto, ProblemDto};
+pub use validate::{field_errors, FieldError};
+
+/// Sent to clients in place of internal error details, which are only logged
+pub(crate) const INTERNAL_MESSAGE: &str = "unexpected error handling request";
+
+/// Sent to clients in place of serialization failure details, which are only logged
+pub(crate) const SERIALIZATION_MESSAGE: &str = "conflicted with a concurrent request, try again";
+
 /// Project level error type
-#[derive(thiserror::Error, Debug, Serialize)]
+#[derive(thiserror::Error, Debug, Serialize, IntoStaticStr)]
 #[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
 pub enum Error {
     #[error("invalid arguments")]
     InvalidArgs { messages: Vec<String> },
+    #[error("validation failed")]
+    ValidationFailed { fields: Vec<FieldError> },
     #[error("internal error: {message}")]
     Internal { message: String },
     #[error("not found error: {message}")]
     NotFound { message: String },
+    #[error("rate limit exceeded, retry after {}s", retry_after_secs(.retry_after))]
+    RateLimited { retry_after: Duration },
+    #[error("request body exceeds {limit} bytes")]
+    PayloadTooLarge { limit: usize },
+    #[error("timed out reading request body")]
+    RequestTimeout,
+    #[error("request timed out after {}", humantime::format_duration(*.after))]
+    Timeout { after: Duration },
+    #[error("service unavailable: {message}")]
+    Unavailable { message: String },
+    #[error("conflict: {message}")]
+    Conflict { message: String },
+    #[error("unprocessable request: {message}")]
+    Unprocessable { message: String },
+    #[error("idempotency key was already used with a different request")]
+    IdempotencyKeyReused,
+    #[error("serialization failure: {message}")]
+    SerializationFailure { message: String },
+    #[error("method {method} not allowed")]
+    MethodNotAllowed { method: String },
+    #[error("unsupported media type: {message}")]
+    UnsupportedMediaType { message: String },
+    #[error("not acceptable: {message}")]
+    NotAcceptable { message: String },
+}
+
+impl Error {
+    /// Count an error by variant as it is returned to a client.
+    pub(crate) fn record(&self) {
+        let kind: &'static str = self.into();
+        metrics::counter!("gsd_errors_total", "kind" => kind).increment(1);
+    }
+}
+
+/// Whole seconds to wait before retrying, rounded up.
+pub(crate) fn retry_after_secs(retry_after: &Duration) -> u64 {
+    retry_after.as_secs_f64().ceil().max(1.0) as u64
 }
@@ -1,26 +1,137 @@
 use super::Error;
-use validator::{ValidationError, ValidationErrors};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+/// A machine readable error for a single invalid field.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path to the field, e.g. `stories[0].name`
+    pub field: String,
+    /// Stable error code, e.g. `length`
+    pub code: String,
+    pub message: String,
+    /// Rule parameters, e.g. `min` and `max` for lengths
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    #[schema(value_type = Object)]
+    pub params: Map<String, Value>,
+}
+
+impl FieldError {
+    /// Create a field error without params.
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+            params: Map::new(),
+        }
+    }
+
+    /// Add a rule parameter.
+    pub fn with_param(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(key.to_owned(), value.into());
+        self
+    }
+}
 
 /// Map validation errors into project errors.
 impl From<ValidationErrors> for Error {
     fn from(errors: ValidationErrors) -> Self {
-        Error::InvalidArgs {
-            messages: errors
-                .field_errors()
-                .iter()
-                .map(|pair| format!("{}: {}", pair.0, summarize(pair.1)))
-                .collect(),
+        Error::ValidationFailed {
+            fields: field_errors("", &errors),
         }
     }
 }
 
-/// Summarize a set of validation errors into a CSV string.
-fn summarize(errors: &[ValidationError]) -> String {
-    let messages: Vec<String> = errors
-        .iter()
-        .map(|error| error.to_owned().message.unwrap_or("invalid field".into()))
-        .map(|s| s.to_string())
+/// Flatten validation errors into field errors, prefixing field names with the location of the
+/// invalid input.
+pub fn field_errors(prefix: &str, errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors: Vec<FieldError> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |error| FieldError {
+                field: format!("{}{}", prefix, field),
+                code: error.code.to_string(),
+                message: error
+                    .message
+                    .as_ref()
+                    .map(|message| message.to_string())
+                    .unwrap_or("invalid field".into()),
+                // Leave out the submitted value, which is echoed back by validator
+                params: error
+                    .params
+                    .iter()
+                    .filter(|(key, _)| *key != "value")
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect(),
+            })
+        })
         .collect();
 
-    messages.join(", ")
+    // Validator keeps fields in a hash map, so sort for a stable order.
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    field_errors
+}
+
+/// Summarize field errors into one message per field, e.g. `name: invalid length`.
+pub(crate) fn summarize(fields: &[FieldError]) -> Vec<String> {
+    let mut messages: Vec<(String, Vec<&str>)> = Vec::new();
+    for error in fields {
+        match messages.last_mut() {
+            Some((field, list)) if *field == error.field => list.push(&error.message),
+            _ => messages.push((error.field.clone(), vec![&error.message])),
+        }
+    }
+    messages
+        .into_iter()
+        .map(|(field, list)| format!("{}: {}", field, list.join(", ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Body {
+        #[validate(length(min = 1, max = 3, message = "invalid length"))]
+        name: String,
+        #[validate(length(min = 2))]
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn field_errors_with_params() {
+        let body = Body {
+            name: "long".into(),
+            tags: vec![],
+        };
+        let errors = body.validate().unwrap_err();
+        let fields = field_errors("stories[0].", &errors);
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].field, "stories[0].name");
+        assert_eq!(fields[0].code, "length");
+        assert_eq!(fields[0].message, "invalid length");
+        assert_eq!(json!(fields[0].params), json!({"min": 1, "max": 3}));
+        assert_eq!(fields[1].field, "stories[0].tags");
+        assert_eq!(fields[1].message, "invalid field");
+
+        assert_eq!(
+            summarize(&fields),
+            vec![
+                "stories[0].name: invalid length",
+                "stories[0].tags: invalid field"
+            ]
+        );
+    }
 }
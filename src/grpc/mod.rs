@@ -0,0 +1,152 @@
+use crate::{api::ApiCtx, domain, error::FieldError, Error, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::sync::Arc;
+use tonic::service::Routes;
+use uuid::Uuid;
+
+/// Types and services generated from the protobuf definitions
+#[allow(clippy::all)]
+pub mod pb {
+    tonic::include_proto!("gsd.v1");
+}
+
+// Story service
+mod story;
+
+// Task service
+mod task;
+
+use pb::{story_service_server::StoryServiceServer, task_service_server::TaskServiceServer};
+use story::StoryRpc;
+use task::TaskRpc;
+
+/// The top-level GSD gRPC service
+pub struct Grpc {
+    ctx: Arc<ApiCtx>,
+}
+
+impl Grpc {
+    /// Create a new service
+    pub fn new(ctx: Arc<ApiCtx>) -> Self {
+        Self { ctx }
+    }
+
+    /// Define gRPC routes for the story and task services.
+    pub fn routes(self) -> Routes {
+        let story_service = StoryServiceServer::new(StoryRpc::new(Arc::clone(&self.ctx)));
+        let task_service = TaskServiceServer::new(TaskRpc::new(self.ctx));
+        Routes::new(story_service).add_service(task_service)
+    }
+}
+
+/// Parse a uuid field from a request message.
+fn parse_id(field: &str, value: &str) -> Result<Uuid> {
+    Uuid::parse_str(value).map_err(|_| Error::InvalidArgs {
+        messages: vec![format!("{}: invalid uuid", field)],
+    })
+}
+
+/// Parse an RFC 3339 timestamp field from a request message.
+fn parse_time(field: &str, value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| Error::ValidationFailed {
+            fields: vec![FieldError::new(field, "timestamp", "invalid timestamp")],
+        })
+}
+
+/// Map story domain objects to messages
+impl From<domain::Story> for pb::Story {
+    fn from(story: domain::Story) -> Self {
+        Self {
+            id: story.id.to_string(),
+            name: story.name,
+            owner: story.owner,
+        }
+    }
+}
+
+/// Map task domain objects to messages
+impl From<domain::Task> for pb::Task {
+    fn from(task: domain::Task) -> Self {
+        let status = pb::Status::from(task.status);
+        Self {
+            id: task.id.to_string(),
+            story_id: task.story_id.to_string(),
+            name: task.name,
+            status: status.into(),
+            due_at: task
+                .due_at
+                .map(|due_at| due_at.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        }
+    }
+}
+
+/// Map status domain objects to messages
+impl From<domain::Status> for pb::Status {
+    fn from(status: domain::Status) -> Self {
+        match status {
+            domain::Status::Incomplete => pb::Status::Incomplete,
+            domain::Status::Complete => pb::Status::Complete,
+        }
+    }
+}
+
+/// Parse a status field from a request message.
+fn parse_status(value: i32) -> Result<domain::Status> {
+    match pb::Status::try_from(value) {
+        Ok(pb::Status::Incomplete) => Ok(domain::Status::Incomplete),
+        Ok(pb::Status::Complete) => Ok(domain::Status::Complete),
+        _ => Err(Error::InvalidArgs {
+            messages: vec!["status: unmatched enum variant".into()],
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_fields_map_to_invalid_argument() {
+        let status = tonic::Status::from(parse_id("story_id", "not-a-uuid").unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "story_id: invalid uuid");
+
+        let status = tonic::Status::from(parse_status(pb::Status::Unspecified.into()).unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        let due_at = parse_time("due_at", "2026-10-19T09:30:00+01:00").unwrap();
+        assert_eq!(due_at.to_rfc3339(), "2026-10-19T08:30:00+00:00");
+
+        let status = tonic::Status::from(parse_time("due_at", "tomorrow").unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "due_at: invalid timestamp");
+    }
+
+    #[test]
+    fn database_details_are_not_sent() {
+        let status = tonic::Status::from(crate::Error::Internal {
+            message: "error returned from database: connection reset".into(),
+        });
+        assert_eq!(status.code(), tonic::Code::Internal);
+        assert_eq!(status.message(), "unexpected error handling request");
+
+        let status = tonic::Status::from(crate::Error::SerializationFailure {
+            message: "could not serialize access due to concurrent update".into(),
+        });
+        assert_eq!(status.code(), tonic::Code::Aborted);
+        assert!(!status.message().contains("serialize access"));
+    }
+
+    #[test]
+    fn status_round_trip() {
+        for status in [domain::Status::Incomplete, domain::Status::Complete] {
+            let value: i32 = pb::Status::from(status).into();
+            assert_eq!(parse_status(value).unwrap(), status);
+        }
+    }
+}
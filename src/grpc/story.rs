@@ -0,0 +1,119 @@
+use crate::{
+    api::{
+        dto::{normalize_owner, CreateStoryBody, Normalize, PatchStoryBody},
+        ApiCtx, BACKLOG,
+    },
+    grpc::{parse_id, pb},
+    repo::read_primary,
+};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use validator::Validate;
+
+/// gRPC story service
+pub struct StoryRpc {
+    ctx: Arc<ApiCtx>,
+}
+
+impl StoryRpc {
+    /// Constructor
+    pub fn new(ctx: Arc<ApiCtx>) -> Self {
+        Self { ctx }
+    }
+}
+
+#[tonic::async_trait]
+impl pb::story_service_server::StoryService for StoryRpc {
+    /// Get story by id
+    async fn get_story(
+        &self,
+        request: Request<pb::GetStoryRequest>,
+    ) -> Result<Response<pb::Story>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("get_story: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        let story = self.ctx.story_repo.fetch(id).await?;
+
+        Ok(Response::new(story.into()))
+    }
+
+    /// Get stories by owner
+    async fn list_stories(
+        &self,
+        request: Request<pb::ListStoriesRequest>,
+    ) -> Result<Response<pb::ListStoriesResponse>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("list_stories: {:?}", request);
+
+        let owner = request
+            .owner
+            .as_deref()
+            .map(normalize_owner)
+            .unwrap_or(BACKLOG.into());
+        let stories = self.ctx.story_repo.fetch_all(owner).await?;
+
+        Ok(Response::new(pb::ListStoriesResponse {
+            stories: stories.into_iter().map(pb::Story::from).collect(),
+        }))
+    }
+
+    /// Create a new story for an owner
+    async fn create_story(
+        &self,
+        request: Request<pb::CreateStoryRequest>,
+    ) -> Result<Response<pb::Story>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("create_story: {:?}", request);
+
+        let body = CreateStoryBody {
+            name: request.name,
+            owner: request.owner,
+        }
+        .normalize();
+        body.validate().map_err(crate::Error::from)?;
+
+        let owner = body.owner.unwrap_or(BACKLOG.into());
+        let story = self.ctx.story_repo.create(body.name, owner).await?;
+
+        Ok(Response::new(story.into()))
+    }
+
+    /// Update a story name and/or owner.
+    async fn update_story(
+        &self,
+        request: Request<pb::UpdateStoryRequest>,
+    ) -> Result<Response<pb::Story>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("update_story: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        let body = PatchStoryBody {
+            name: request.name,
+            owner: request.owner,
+        }
+        .normalize();
+        body.validate().map_err(crate::Error::from)?;
+
+        let story = read_primary(self.ctx.story_repo.fetch(id)).await?;
+        let (name, owner) = body.unwrap(story);
+        let story = self.ctx.story_repo.update(id, name, owner).await?;
+
+        Ok(Response::new(story.into()))
+    }
+
+    /// Delete a story by id
+    async fn delete_story(
+        &self,
+        request: Request<pb::DeleteStoryRequest>,
+    ) -> Result<Response<pb::DeleteStoryResponse>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("delete_story: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        read_primary(self.ctx.story_repo.fetch(id)).await?;
+        self.ctx.story_repo.delete(id).await?;
+
+        Ok(Response::new(pb::DeleteStoryResponse {}))
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    api::{
+        dto::{CreateTaskBody, Normalize, PatchTaskBody},
+        ApiCtx,
+    },
+    grpc::{parse_id, parse_status, parse_time, pb},
+    repo::read_primary,
+};
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use validator::Validate;
+
+/// gRPC task service
+pub struct TaskRpc {
+    ctx: Arc<ApiCtx>,
+}
+
+impl TaskRpc {
+    /// Constructor
+    pub fn new(ctx: Arc<ApiCtx>) -> Self {
+        Self { ctx }
+    }
+}
+
+#[tonic::async_trait]
+impl pb::task_service_server::TaskService for TaskRpc {
+    type ListTasksStream = BoxStream<'static, Result<pb::Task, Status>>;
+
+    /// Get task by id
+    async fn get_task(
+        &self,
+        request: Request<pb::GetTaskRequest>,
+    ) -> Result<Response<pb::Task>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("get_task: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        let task = self.ctx.task_repo.fetch(id).await?;
+
+        Ok(Response::new(task.into()))
+    }
+
+    /// Stream tasks for a story
+    async fn list_tasks(
+        &self,
+        request: Request<pb::ListTasksRequest>,
+    ) -> Result<Response<Self::ListTasksStream>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("list_tasks: {:?}", request);
+
+        let story_id = parse_id("story_id", &request.story_id)?;
+        self.ctx.story_repo.fetch(story_id).await?;
+
+        let stream = self.ctx.task_repo.stream_all(story_id);
+        let stream = stream.map_ok(pb::Task::from).map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    /// Create a new task
+    async fn create_task(
+        &self,
+        request: Request<pb::CreateTaskRequest>,
+    ) -> Result<Response<pb::Task>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("create_task: {:?}", request);
+
+        let due_at = request.due_at.as_deref();
+        let body = CreateTaskBody {
+            name: request.name,
+            story_id: parse_id("story_id", &request.story_id)?,
+            due_at: due_at.map(|t| parse_time("due_at", t)).transpose()?,
+        }
+        .normalize();
+        body.validate().map_err(crate::Error::from)?;
+
+        read_primary(self.ctx.story_repo.fetch(body.story_id)).await?;
+        let task_repo = &self.ctx.task_repo;
+        let task = task_repo
+            .create(body.story_id, body.name, body.due_at)
+            .await?;
+
+        Ok(Response::new(task.into()))
+    }
+
+    /// Update a task name, status and/or due date.
+    async fn update_task(
+        &self,
+        request: Request<pb::UpdateTaskRequest>,
+    ) -> Result<Response<pb::Task>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("update_task: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        let status = request.status.map(parse_status).transpose()?;
+        let due_at = match request.due_at.as_deref() {
+            Some("") => Some(None),
+            Some(due_at) => Some(Some(parse_time("due_at", due_at)?)),
+            None => None,
+        };
+        let body = PatchTaskBody {
+            name: request.name,
+            status: status.map(|s| s.to_string()),
+            due_at,
+        }
+        .normalize();
+        body.validate().map_err(crate::Error::from)?;
+
+        let task = read_primary(self.ctx.task_repo.fetch(id)).await?;
+        let (name, status, due_at) = body.unwrap(task);
+        let task = self.ctx.task_repo.update(id, name, status, due_at).await?;
+
+        Ok(Response::new(task.into()))
+    }
+
+    /// Delete a task by id
+    async fn delete_task(
+        &self,
+        request: Request<pb::DeleteTaskRequest>,
+    ) -> Result<Response<pb::DeleteTaskResponse>, Status> {
+        let request = request.into_inner();
+        tracing::debug!("delete_task: {:?}", request);
+
+        let id = parse_id("id", &request.id)?;
+        read_primary(self.ctx.task_repo.fetch(id)).await?;
+        self.ctx.task_repo.delete(id).await?;
+
+        Ok(Response::new(pb::DeleteTaskResponse {}))
+    }
+}
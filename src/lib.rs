@@ -1,8 +1,14 @@
+pub mod admin;
 pub mod api;
+pub mod cmd;
 pub mod config;
 pub mod domain;
 pub mod error;
+pub mod grpc;
+pub mod notify;
 pub mod repo;
+pub mod telemetry;
+pub mod tls;
 
 /// Expose error at the top level
 pub use error::Error;
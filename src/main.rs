@@ -1,46 +1,85 @@
-use gsd::{
-    api::{Api, ApiCtx},
-    config::Config,
-};
+use gsd::{cmd, config::Config, telemetry};
 
-use axum::Router;
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
-use sqlx::migrate::Migrator;
-use std::{error::Error, sync::Arc};
+use std::{error::Error, path::PathBuf, process, sync::Arc, time::Duration};
 
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-// Embed migrations into the server binary.
-pub static MIGRATOR: Migrator = sqlx::migrate!();
+/// The GSD web-service and its maintenance tasks.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML config file, overridden by env vars
+    #[arg(long, env = "GSD_CONFIG", global = true)]
+    config: Option<PathBuf>,
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Load env vars, init logging
-    dotenv().ok();
-    env_logger::init();
+    /// Defaults to `serve` when not given
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    // Load config
-    let config = Arc::new(Config::default());
-    log::debug!("Loaded config = {:?}", config);
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the web-service
+    Serve {
+        /// Don't apply pending migrations on startup
+        #[arg(long)]
+        no_migrate: bool,
+    },
+    /// Manage database migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Permanently delete stories and tasks soft deleted before a cutoff
+    Purge {
+        /// Age of deleted data to purge, e.g. `30d` or `12h`
+        #[arg(long, value_parser = humantime::parse_duration)]
+        older_than: Duration,
+    },
+    /// Create demo stories and tasks
+    Seed {
+        /// Owner of the demo stories
+        #[arg(long, default_value = "backlog")]
+        owner: String,
+    },
+    /// Check configuration and database connectivity
+    CheckConfig,
+}
 
-    // Create pg connection pool
-    let pool = config
-        .db_pool_opts()
-        .connect(config.db_connection_string().as_ref())
-        .await?;
+#[derive(Debug, Subcommand)]
+enum MigrateCommand {
+    /// Apply all pending migrations
+    Up,
+    /// Show which migrations have been applied
+    Status,
+}
 
-    log::info!("Running migrations");
-    MIGRATOR.run(&pool).await?;
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    // Load env vars
+    dotenv().ok();
 
-    // Set up API
-    let ctx = ApiCtx::new(Arc::clone(&config), Arc::new(pool));
-    let api = Api::new(Arc::new(ctx));
-    let router = Router::new().nest(&config.url_base, api.routes());
+    let cli = Cli::parse();
 
-    // Start server
-    log::info!("Server listening on {}", config.listen_addr);
-    axum::serve(config.tcp_listener(), router).await?;
+    // Load config, init tracing
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => Arc::new(config),
+        Err(err) => {
+            eprintln!("{}", err);
+            // EX_CONFIG from sysexits.h
+            process::exit(78);
+        }
+    };
+    let _telemetry = telemetry::init(&config)?;
+    tracing::debug!("Loaded config = {:?}", config);
 
-    Ok(())
+    match cli.command.unwrap_or(Command::Serve { no_migrate: false }) {
+        Command::Serve { no_migrate } => cmd::serve(config, !no_migrate).await,
+        Command::Migrate(MigrateCommand::Up) => cmd::migrate_up(config).await,
+        Command::Migrate(MigrateCommand::Status) => cmd::migrate_status(config).await,
+        Command::Purge { older_than } => cmd::purge(config, older_than).await,
+        Command::Seed { owner } => cmd::seed(config, owner).await,
+        Command::CheckConfig => cmd::check_config(config).await,
+    }
 }
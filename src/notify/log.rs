@@ -0,0 +1,21 @@
+use crate::{domain::Reminder, notify::Notifier, Result};
+use axum::async_trait;
+
+/// Writes reminders to the service log.
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, reminder: &Reminder) -> Result<()> {
+        tracing::info!(
+            task_id = %reminder.task_id,
+            story_id = %reminder.story_id,
+            owner = %reminder.owner,
+            due_at = %reminder.due_local().to_rfc3339(),
+            "{}",
+            reminder.subject()
+        );
+        Ok(())
+    }
+}
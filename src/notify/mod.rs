@@ -0,0 +1,183 @@
+use crate::{
+    config::{Config, NotifierKind},
+    domain::Reminder,
+    repo::ReminderRepo,
+    Result,
+};
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+// Reminders written to the log
+mod log;
+
+// Reminders sent by email
+mod smtp;
+
+pub use log::LogNotifier;
+pub use smtp::SmtpNotifier;
+
+/// Shortest wait before sending reminders again after the notifier fails
+const MIN_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Longest wait before sending reminders again while the notifier keeps failing
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+/// Sends reminders to task owners.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Send a reminder, failing when it should be retried later.
+    async fn notify(&self, reminder: &Reminder) -> Result<()>;
+}
+
+/// Create the notifier chosen by config.
+pub fn notifier(config: &Config) -> Result<Arc<dyn Notifier>> {
+    match config.notifier {
+        NotifierKind::Log => Ok(Arc::new(LogNotifier)),
+        NotifierKind::Smtp => Ok(Arc::new(SmtpNotifier::new(config)?)),
+    }
+}
+
+/// Sends reminders for tasks as their due dates approach.
+pub struct Reminders {
+    repo: Arc<ReminderRepo>,
+    notifier: Arc<dyn Notifier>,
+    lead: Duration,
+    backoff: Mutex<Backoff>,
+}
+
+impl Reminders {
+    /// Constructor, reminding owners the given time before tasks are due.
+    pub fn new(repo: Arc<ReminderRepo>, notifier: Arc<dyn Notifier>, lead: Duration) -> Self {
+        Self {
+            repo,
+            notifier,
+            lead,
+            backoff: Mutex::new(Backoff::default()),
+        }
+    }
+
+    /// Send reminders for tasks due within the lead time, returning how many were sent. When a
+    /// reminder fails, it and the rest of the batch are released to be retried on a later pass,
+    /// and passes are skipped for a while, backing off further each time the notifier fails again.
+    pub async fn send_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        if let Some(until) = self.backoff().retry_at(now) {
+            tracing::debug!("Skipping reminders until {}", until);
+            return Ok(0);
+        }
+
+        let lead = chrono::Duration::from_std(self.lead).unwrap_or(chrono::Duration::MAX);
+        let due_before = now
+            .checked_add_signed(lead)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let mut sent = 0;
+        let mut reminders = self.repo.claim_due(due_before).await?.into_iter();
+        for reminder in reminders.by_ref() {
+            match self.notifier.notify(&reminder).await {
+                Ok(()) => {
+                    self.mark_sent(&reminder).await;
+                    sent += 1;
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to send reminder for {}: {}", reminder.task_id, err);
+                    let until = self.backoff().fail(now);
+                    tracing::warn!("Backing off sending reminders until {}", until);
+                    self.release(&reminder).await;
+                    for reminder in reminders {
+                        self.release(&reminder).await;
+                    }
+                    return Ok(sent);
+                }
+            }
+        }
+
+        self.backoff().reset();
+        Ok(sent)
+    }
+
+    /// Mark a claimed reminder sent. When that fails, the reminder is sent again once its claim
+    /// expires, so failures are logged rather than stopping the batch.
+    async fn mark_sent(&self, reminder: &Reminder) {
+        if let Err(err) = self.repo.mark_sent(reminder.task_id, reminder.due_at).await {
+            tracing::error!(
+                "Failed to mark reminder sent for {}: {}",
+                reminder.task_id,
+                err
+            );
+        }
+    }
+
+    /// Release a claimed reminder to be retried, logging failures so the rest are still released.
+    async fn release(&self, reminder: &Reminder) {
+        if let Err(err) = self.repo.release(reminder.task_id).await {
+            tracing::error!(
+                "Failed to release reminder for {}: {}",
+                reminder.task_id,
+                err
+            );
+        }
+    }
+
+    /// Lock the backoff state.
+    fn backoff(&self) -> MutexGuard<'_, Backoff> {
+        self.backoff.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Consecutive notifier failures, and when to try again.
+#[derive(Debug, Default)]
+struct Backoff {
+    failures: u32,
+    until: Option<DateTime<Utc>>,
+}
+
+impl Backoff {
+    /// When to try again, while still backing off.
+    fn retry_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.until.filter(|until| now < *until)
+    }
+
+    /// Record a failure, doubling the wait up to the maximum, and return when to try again.
+    fn fail(&mut self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let wait = MIN_BACKOFF
+            .saturating_mul(2u32.saturating_pow(self.failures))
+            .min(MAX_BACKOFF);
+        self.failures = self.failures.saturating_add(1);
+        let until = now + chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::MAX);
+        self.until = Some(until);
+        until
+    }
+
+    /// Record a pass without failures.
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_reset() {
+        let now: DateTime<Utc> = "2026-10-19T09:00:00Z".parse().unwrap();
+        let mut backoff = Backoff::default();
+        assert_eq!(backoff.retry_at(now), None);
+
+        let waits: Vec<i64> = (0..8)
+            .map(|_| (backoff.fail(now) - now).num_seconds())
+            .collect();
+        assert_eq!(waits, vec![30, 60, 120, 240, 480, 960, 1800, 1800]);
+
+        let until = backoff.retry_at(now).unwrap();
+        assert_eq!(backoff.retry_at(until), None);
+
+        backoff.reset();
+        assert_eq!(backoff.retry_at(now), None);
+        assert_eq!((backoff.fail(now) - now).num_seconds(), 30);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{
+    config::{Config, SmtpSecurity},
+    domain::Reminder,
+    notify::Notifier,
+    Error, Result,
+};
+use axum::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+/// Emails reminders to owners that have an email address.
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    /// Create a notifier for the SMTP server in config.
+    pub fn new(config: &Config) -> Result<Self> {
+        let (Some(host), Some(from)) = (&config.smtp_host, &config.smtp_from) else {
+            return Err(Error::Internal {
+                message: "SMTP_HOST and SMTP_FROM are required to send email".into(),
+            });
+        };
+
+        let builder = match config.smtp_security {
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                host,
+            )),
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        }
+        .map_err(|err| Error::Internal {
+            message: format!("smtp: {}", err),
+        })?
+        .port(config.smtp_port);
+
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose().to_owned(),
+            )),
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    /// Email a reminder. Owners without an email address are skipped, and so are reminders the
+    /// server permanently rejects, since retrying won't help.
+    async fn notify(&self, reminder: &Reminder) -> Result<()> {
+        let Some(email) = &reminder.email else {
+            tracing::debug!("No email for owner: {}", reminder.owner);
+            return Ok(());
+        };
+
+        let to: Mailbox = email.parse().map_err(|err| Error::Internal {
+            message: format!("invalid email for owner {}: {}", reminder.owner, err),
+        })?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(reminder.subject())
+            .header(ContentType::TEXT_PLAIN)
+            .body(reminder.body())
+            .map_err(|err| Error::Internal {
+                message: format!("smtp: {}", err),
+            })?;
+
+        match self.transport.send(message).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.is_permanent() => {
+                tracing::warn!("Reminder rejected for {}: {}", reminder.owner, err);
+                Ok(())
+            }
+            Err(err) => Err(Error::Unavailable {
+                message: format!("smtp: {}", err),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Tz;
+    use std::collections::HashMap;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+    use uuid::Uuid;
+
+    /// A local SMTP server that accepts one connection, replying to `RCPT TO` with the given
+    /// code, and returns the commands and message it received.
+    async fn smtp_stub(rcpt_code: u16) -> (u16, JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let (mut commands, mut data) = (Vec::new(), String::new());
+
+            writer.write_all(b"220 stub ESMTP\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let verb = line.split_whitespace().next().unwrap_or("").to_uppercase();
+                commands.push(verb.clone());
+                let reply = match verb.as_str() {
+                    "EHLO" => "250 stub".to_string(),
+                    "RCPT" => format!("{} recipient", rcpt_code),
+                    "DATA" => {
+                        writer.write_all(b"354 go ahead\r\n").await.unwrap();
+                        while let Some(line) = lines.next_line().await.unwrap() {
+                            if line == "." {
+                                break;
+                            }
+                            data.push_str(&line);
+                            data.push('\n');
+                        }
+                        "250 queued".to_string()
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => "250 ok".to_string(),
+                };
+                writer
+                    .write_all(format!("{}\r\n", reply).as_bytes())
+                    .await
+                    .unwrap();
+            }
+            (commands, data)
+        });
+        (port, handle)
+    }
+
+    fn notifier(port: u16) -> SmtpNotifier {
+        let vars = [
+            (
+                "DATABASE_URL",
+                "postgres://gsd:pw@localhost/gsd".to_string(),
+            ),
+            ("DB_SCHEMA", "gsd".into()),
+            ("NOTIFIER", "smtp".into()),
+            ("SMTP_HOST", "127.0.0.1".into()),
+            ("SMTP_PORT", port.to_string()),
+            ("SMTP_SECURITY", "none".into()),
+            ("SMTP_FROM", "GSD <gsd@example.com>".into()),
+        ];
+        let env: HashMap<String, String> = vars.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        SmtpNotifier::new(&Config::from_sources(env, None).unwrap()).unwrap()
+    }
+
+    fn reminder(email: Option<&str>) -> Reminder {
+        Reminder {
+            task_id: Uuid::new_v4(),
+            task_name: "Renew passport".into(),
+            story_id: Uuid::new_v4(),
+            story_name: "Travel".into(),
+            owner: "alice".into(),
+            email: email.map(String::from),
+            timezone: Tz::Europe__Dublin,
+            due_at: "2026-10-19T08:30:00Z".parse().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_reminder_email() {
+        let (port, stub) = smtp_stub(250).await;
+        let reminder = reminder(Some("alice@example.com"));
+        notifier(port).notify(&reminder).await.unwrap();
+
+        let (commands, data) = stub.await.unwrap();
+        assert_eq!(commands, ["EHLO", "MAIL", "RCPT", "DATA", "QUIT"]);
+        assert!(data.contains("To: alice@example.com"));
+        assert!(data.contains("Subject: Reminder: Renew passport is due Mon 19 Oct 2026 09:30 IST"));
+        assert!(data.contains(&reminder.task_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn skip_owners_without_email() {
+        // Nothing is listening, so sending would fail
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let notifier = notifier(port);
+        assert!(notifier.notify(&reminder(None)).await.is_ok());
+
+        let err = notifier
+            .notify(&reminder(Some("alice@example.com")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Unavailable { .. }));
+    }
+
+    #[tokio::test]
+    async fn drop_rejected_reminders() {
+        let (port, stub) = smtp_stub(550).await;
+        let reminder = reminder(Some("nobody@example.com"));
+        assert!(notifier(port).notify(&reminder).await.is_ok());
+        let (commands, _) = stub.await.unwrap();
+        assert!(!commands.contains(&"DATA".to_string()));
+    }
+}
@@ -0,0 +1,373 @@
+use crate::{
+    repo::{begin_serializable, retry_serialization, Replica, MIGRATOR},
+    Result,
+};
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::instrument;
+
+/// The state of an embedded migration in the database.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    ChecksumMismatch,
+}
+
+/// An embedded migration and its state in the database.
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Owners rewritten to their normalized form, and owners left as they were because their
+/// normalized form is too long to store.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct OwnerBackfill {
+    pub renamed: u64,
+    pub skipped: Vec<String>,
+}
+
+/// Connection pool usage.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+}
+
+/// Longest owner that can be stored, matching `stories_owner_check` and the `owners` table
+const MAX_OWNER_LEN: usize = 100;
+
+/// Backfill recorded once stored owners are normalized
+const NORMALIZE_OWNERS: &str = "normalize_owners";
+
+/// Concrete maintenance related database logic
+pub struct AdminRepo {
+    db: Arc<PgPool>,
+    replica: Option<Arc<Replica>>,
+}
+
+impl AdminRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db, replica: None }
+    }
+
+    /// Constructor that can also check a read replica.
+    pub fn with_replica(db: Arc<PgPool>, replica: Option<Arc<Replica>>) -> Self {
+        Self { db, replica }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl AdminRepo {
+    /// Get the number of open and idle connections in the pool.
+    pub fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.db.size(),
+            idle: self.db.num_idle(),
+        }
+    }
+
+    /// Run a trivial query, returning the round trip time.
+    #[instrument(skip(self))]
+    pub async fn ping(&self) -> Result<Duration> {
+        tracing::debug!("ping");
+
+        let start = Instant::now();
+        sqlx::query("SELECT 1").execute(self.db_ref()).await?;
+        Ok(start.elapsed())
+    }
+
+    /// Run a trivial query on the read replica, if there is one, so reads only use it while it
+    /// answers.
+    #[instrument(skip(self))]
+    pub async fn ping_replica(&self) -> Option<Result<Duration>> {
+        tracing::debug!("ping_replica");
+
+        let replica = self.replica.as_deref()?;
+        let start = Instant::now();
+        let result = sqlx::query("SELECT 1").execute(replica.pool()).await;
+        replica.set_available(result.is_ok());
+        Some(result.map(|_| start.elapsed()).map_err(Into::into))
+    }
+
+    /// Count incomplete tasks that have not been deleted.
+    #[instrument(skip(self))]
+    pub async fn open_tasks(&self) -> Result<i64> {
+        tracing::debug!("open_tasks");
+
+        let sql = r#"
+            SELECT count(*) FROM tasks
+            WHERE status = 'incomplete' AND deleted_at IS NULL
+        "#;
+        let count = sqlx::query_scalar(sql).fetch_one(self.db_ref()).await?;
+
+        Ok(count)
+    }
+
+    /// Compare embedded migrations with those applied to the database.
+    #[instrument(skip(self))]
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        tracing::debug!("migration_status");
+
+        let exists_sql = "SELECT to_regclass('_sqlx_migrations') IS NOT NULL";
+        let exists: bool = sqlx::query_scalar(exists_sql)
+            .fetch_one(self.db_ref())
+            .await?;
+
+        let mut applied = HashMap::new();
+        if exists {
+            let sql = r#"
+                SELECT version, checksum
+                FROM _sqlx_migrations
+                WHERE success
+            "#;
+            let rows: Vec<(i64, Vec<u8>)> = sqlx::query_as(sql).fetch_all(self.db_ref()).await?;
+            applied.extend(rows);
+        }
+
+        let status = MIGRATOR
+            .iter()
+            .map(|migration| {
+                let state = match applied.get(&migration.version) {
+                    None => MigrationState::Pending,
+                    Some(checksum) if *checksum == *migration.checksum => MigrationState::Applied,
+                    Some(_) => MigrationState::ChecksumMismatch,
+                };
+                MigrationStatus {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    state,
+                }
+            })
+            .collect();
+
+        Ok(status)
+    }
+
+    /// Rewrite owners that differ from their normalized form, such as owners stored before they
+    /// were case folded, keeping settings already saved for the normalized owner. Owners that
+    /// would be too long once normalized are skipped. This runs once, returning `None` when it
+    /// already has.
+    #[instrument(skip(self, normalize))]
+    pub async fn normalize_owners(
+        &self,
+        normalize: impl Fn(&str) -> String,
+    ) -> Result<Option<OwnerBackfill>> {
+        tracing::debug!("normalize_owners");
+
+        let sql = "SELECT EXISTS (SELECT 1 FROM backfills WHERE name = $1)";
+        let done: bool = sqlx::query_scalar(sql)
+            .bind(NORMALIZE_OWNERS)
+            .fetch_one(self.db_ref())
+            .await?;
+        if done {
+            return Ok(None);
+        }
+
+        let sql = "SELECT owner FROM stories UNION SELECT owner FROM owners";
+        let owners: Vec<String> = sqlx::query_scalar(sql).fetch_all(self.db_ref()).await?;
+
+        let mut backfill = OwnerBackfill::default();
+        for owner in owners {
+            let normalized = normalize(&owner);
+            if normalized == owner {
+                continue;
+            }
+            if !(1..=MAX_OWNER_LEN).contains(&normalized.chars().count()) {
+                backfill.skipped.push(owner);
+                continue;
+            }
+            self.rename_owner(&owner, &normalized).await?;
+            backfill.renamed += 1;
+        }
+
+        // Recorded last, so an interrupted backfill runs again
+        let sql = "INSERT INTO backfills (name) VALUES ($1) ON CONFLICT (name) DO NOTHING";
+        sqlx::query(sql)
+            .bind(NORMALIZE_OWNERS)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(Some(backfill))
+    }
+
+    /// Move an owner's stories and settings to another owner in a single transaction.
+    async fn rename_owner(&self, owner: &str, normalized: &str) -> Result<()> {
+        let mut transaction = self.db.begin().await?;
+
+        let stories_sql = r#"
+            UPDATE stories SET owner = $2, updated_at = now()
+            WHERE owner = $1
+        "#;
+        sqlx::query(stories_sql)
+            .bind(owner)
+            .bind(normalized)
+            .execute(&mut *transaction)
+            .await?;
+
+        let settings_sql = r#"
+            INSERT INTO owners (owner, timezone, email, created_at, updated_at)
+            SELECT $2, timezone, email, created_at, now()
+            FROM owners
+            WHERE owner = $1
+            ON CONFLICT (owner) DO NOTHING
+        "#;
+        sqlx::query(settings_sql)
+            .bind(owner)
+            .bind(normalized)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("DELETE FROM owners WHERE owner = $1")
+            .bind(owner)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Permanently delete stories and tasks that were soft deleted before a cutoff.
+    /// Returns the number of deleted stories and tasks.
+    #[instrument(skip(self))]
+    pub async fn purge(&self, older_than: Duration) -> Result<(u64, u64)> {
+        tracing::debug!("purge: {:?}", older_than);
+        retry_serialization(|| self.try_purge(older_than)).await
+    }
+
+    /// Permanently delete soft deleted stories and tasks in a single transaction.
+    async fn try_purge(&self, older_than: Duration) -> Result<(u64, u64)> {
+        let mut transaction = begin_serializable(self.db_ref()).await?;
+
+        let purge_tasks_sql = r#"
+            DELETE FROM tasks
+            WHERE deleted_at < now() - make_interval(secs => $1)
+            OR story_id IN (
+                SELECT id FROM stories
+                WHERE deleted_at < now() - make_interval(secs => $1)
+            )
+        "#;
+        let purge_tasks_result = sqlx::query(purge_tasks_sql)
+            .bind(older_than.as_secs_f64())
+            .execute(&mut *transaction)
+            .await?;
+
+        let purge_stories_sql = r#"
+            DELETE FROM stories
+            WHERE deleted_at < now() - make_interval(secs => $1)
+        "#;
+        let purge_stories_result = sqlx::query(purge_stories_sql)
+            .bind(older_than.as_secs_f64())
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok((
+            purge_stories_result.rows_affected(),
+            purge_tasks_result.rows_affected(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::dto::normalize_owner;
+    use crate::repo::{tests, StoryRepo, TaskRepo};
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+        let story_repo = StoryRepo::new(Arc::clone(&pool));
+
+        // Set up repo under test
+        let admin_repo = AdminRepo::new(Arc::clone(&pool));
+
+        // All migrations were applied during setup
+        let status = admin_repo.migration_status().await.unwrap();
+        assert!(status.iter().all(|m| m.state == MigrationState::Applied));
+        admin_repo.ping().await.unwrap();
+
+        // Soft delete a story, then purge it
+        let owner = "github.com/carp-cobain".to_string();
+        let story = story_repo
+            .create("Books To Read".into(), owner)
+            .await
+            .unwrap();
+        let task_repo = TaskRepo::new(Arc::clone(&pool));
+        task_repo
+            .create(story.id, "Suttree".into(), None)
+            .await
+            .unwrap();
+        assert_eq!(admin_repo.open_tasks().await.unwrap(), 1);
+
+        story_repo.delete(story.id).await.unwrap();
+        assert_eq!(admin_repo.open_tasks().await.unwrap(), 0);
+        let purged = admin_repo.purge(Duration::ZERO).await.unwrap();
+        assert_eq!(purged, (1, 1));
+
+        // Owners stored before they were case folded are rewritten, keeping existing settings
+        sqlx::query("INSERT INTO stories (name, owner) VALUES ('Suttree', 'Straße'), ('Outer Dark', 'strasse')")
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+
+        // ...unless case folding makes them too long to store
+        let long = "ß".repeat(60);
+        sqlx::query("INSERT INTO stories (name, owner) VALUES ('Child of God', $1)")
+            .bind(&long)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO owners (owner, timezone) VALUES ('Straße', 'Europe/Berlin'), ('strasse', 'UTC')")
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        let backfill = admin_repo.normalize_owners(normalize_owner).await.unwrap();
+        assert_eq!(
+            backfill,
+            Some(OwnerBackfill {
+                renamed: 1,
+                skipped: vec![long.clone()],
+            })
+        );
+        assert_eq!(
+            story_repo.fetch_all("strasse".into()).await.unwrap().len(),
+            2
+        );
+        let timezones: Vec<String> = sqlx::query_scalar("SELECT timezone FROM owners")
+            .fetch_all(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(timezones, vec!["UTC"]);
+        assert_eq!(story_repo.fetch_all(long).await.unwrap().len(), 1);
+
+        // The backfill only runs once
+        assert_eq!(
+            admin_repo.normalize_owners(normalize_owner).await.unwrap(),
+            None
+        );
+    }
+}
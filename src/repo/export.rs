@@ -0,0 +1,254 @@
+use crate::{
+    domain::{Export, ExportStory, ExportTask, Status, EXPORT_VERSION},
+    repo::{begin_serializable, retry_serialization},
+    Result,
+};
+use futures_util::TryStreamExt;
+use sqlx::{postgres::PgPool, Row};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Concrete export and import related database logic
+pub struct ExportRepo {
+    db: Arc<PgPool>,
+}
+
+impl ExportRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl ExportRepo {
+    /// Select all stories and tasks for an owner into an export document.
+    #[instrument(skip(self))]
+    pub async fn export(&self, owner: String, include_deleted: bool) -> Result<Export> {
+        tracing::debug!("export: {}, {}", owner, include_deleted);
+
+        let stories_sql = r#"
+            SELECT id, name, deleted_at IS NOT NULL AS deleted
+            FROM stories
+            WHERE owner = $1 AND ($2 OR deleted_at IS NULL)
+            ORDER BY created_at ASC
+        "#;
+
+        let mut result_set = sqlx::query(stories_sql)
+            .bind(&owner)
+            .bind(include_deleted)
+            .fetch(self.db_ref());
+
+        let mut stories = Vec::new();
+        while let Some(row) = result_set.try_next().await? {
+            stories.push(ExportStory {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+                deleted: row.try_get("deleted")?,
+                tasks: Vec::new(),
+            });
+        }
+        drop(result_set);
+
+        let tasks_sql = r#"
+            SELECT id, story_id, name, status, due_at, deleted_at IS NOT NULL AS deleted
+            FROM tasks
+            WHERE story_id = ANY($1) AND ($2 OR deleted_at IS NULL)
+            ORDER BY created_at ASC
+        "#;
+
+        let story_ids: Vec<Uuid> = stories.iter().map(|story| story.id).collect();
+        let mut result_set = sqlx::query(tasks_sql)
+            .bind(&story_ids)
+            .bind(include_deleted)
+            .fetch(self.db_ref());
+
+        let mut tasks: HashMap<Uuid, Vec<ExportTask>> = HashMap::new();
+        while let Some(row) = result_set.try_next().await? {
+            let story_id: Uuid = row.try_get("story_id")?;
+            let status: String = row.try_get("status")?;
+            let status =
+                Status::from_str(&status).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+            tasks.entry(story_id).or_default().push(ExportTask {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+                status,
+                due_at: row.try_get("due_at")?,
+                deleted: row.try_get("deleted")?,
+            });
+        }
+
+        for story in stories.iter_mut() {
+            story.tasks = tasks.remove(&story.id).unwrap_or_default();
+        }
+
+        Ok(Export {
+            version: EXPORT_VERSION,
+            owner,
+            stories,
+        })
+    }
+
+    /// Insert all stories and tasks from an export document for an owner in a single transaction,
+    /// either preserving the document ids or letting the database generate new ones.
+    #[instrument(skip(self, export))]
+    pub async fn import(
+        &self,
+        owner: String,
+        export: Export,
+        preserve_ids: bool,
+    ) -> Result<Export> {
+        tracing::debug!("import: {}, {}", owner, preserve_ids);
+        retry_serialization(|| self.try_import(owner.clone(), export.clone(), preserve_ids)).await
+    }
+
+    /// Insert an export document in a single transaction.
+    async fn try_import(
+        &self,
+        owner: String,
+        export: Export,
+        preserve_ids: bool,
+    ) -> Result<Export> {
+        let mut transaction = begin_serializable(self.db_ref()).await?;
+
+        // NOTE: clock_timestamp() keeps document order, since now() is fixed per transaction.
+        let story_sql = r#"
+            INSERT INTO stories (id, name, owner, created_at, deleted_at)
+            VALUES (
+                COALESCE($1, gen_random_uuid()), $2, $3,
+                clock_timestamp(), CASE WHEN $4 THEN now() END
+            )
+            RETURNING id
+        "#;
+
+        let task_sql = r#"
+            INSERT INTO tasks (id, story_id, name, status, due_at, created_at, deleted_at)
+            VALUES (
+                COALESCE($1, gen_random_uuid()), $2, $3, $4, $5,
+                clock_timestamp(), CASE WHEN $6 THEN now() END
+            )
+            RETURNING id
+        "#;
+
+        let mut stories = Vec::with_capacity(export.stories.len());
+        for story in export.stories {
+            let story_id: Uuid = sqlx::query_scalar(story_sql)
+                .bind(preserve_ids.then_some(story.id))
+                .bind(&story.name)
+                .bind(&owner)
+                .bind(story.deleted)
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            let mut tasks = Vec::with_capacity(story.tasks.len());
+            for task in story.tasks {
+                let task_id: Uuid = sqlx::query_scalar(task_sql)
+                    .bind(preserve_ids.then_some(task.id))
+                    .bind(story_id)
+                    .bind(&task.name)
+                    .bind(task.status.to_string())
+                    .bind(task.due_at)
+                    .bind(task.deleted)
+                    .fetch_one(&mut *transaction)
+                    .await?;
+                tasks.push(ExportTask {
+                    id: task_id,
+                    ..task
+                });
+            }
+
+            stories.push(ExportStory {
+                id: story_id,
+                tasks,
+                ..story
+            });
+        }
+
+        transaction.commit().await?;
+
+        Ok(Export {
+            version: EXPORT_VERSION,
+            owner,
+            stories,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::{tests, StoryRepo, TaskRepo};
+    use chrono::Utc;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+        let story_repo = StoryRepo::new(Arc::clone(&pool));
+        let task_repo = TaskRepo::new(Arc::clone(&pool));
+
+        // Set up repo under test
+        let export_repo = ExportRepo::new(Arc::clone(&pool));
+
+        // Set up a story with a deleted task
+        let owner = "github.com/carp-cobain".to_string();
+        let story = story_repo
+            .create("Books To Read".to_string(), owner.clone())
+            .await
+            .unwrap();
+        task_repo
+            .create(story.id, "Suttree".into(), Some(Utc::now()))
+            .await
+            .unwrap();
+        let task = task_repo
+            .create(story.id, "Outer Dark".into(), None)
+            .await
+            .unwrap();
+        task_repo.delete(task.id).await.unwrap();
+
+        // Export with and without deleted tasks
+        let export = export_repo.export(owner.clone(), false).await.unwrap();
+        assert_eq!(export.stories.len(), 1);
+        assert_eq!(export.stories[0].tasks.len(), 1);
+        let export = export_repo.export(owner.clone(), true).await.unwrap();
+        assert_eq!(export.stories[0].tasks.len(), 2);
+
+        // Import into a new owner with regenerated ids
+        let other = "github.com/carp-cobain/other".to_string();
+        let imported = export_repo
+            .import(other.clone(), export, false)
+            .await
+            .unwrap();
+        assert_ne!(imported.stories[0].id, story.id);
+        assert_eq!(
+            export_repo.export(other.clone(), true).await.unwrap(),
+            imported
+        );
+
+        // Preserving ids that are taken conflicts, without the constraint details
+        let err = export_repo
+            .import(other, imported.clone(), true)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "conflict: conflicts with an existing record"
+        );
+
+        // Cleanup
+        story_repo.delete(story.id).await.unwrap();
+        story_repo.delete(imported.stories[0].id).await.unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+use crate::Result;
+use sqlx::{postgres::PgPool, FromRow};
+use std::{sync::Arc, time::Duration};
+use tracing::instrument;
+
+/// A stored request for an idempotency key, with its response once complete.
+#[derive(Debug, FromRow)]
+pub struct IdempotentRequest {
+    pub request_hash: Vec<u8>,
+    pub status: Option<i16>,
+    pub content_type: Option<String>,
+    pub response: Option<Vec<u8>>,
+}
+
+/// The outcome of claiming an idempotency key.
+#[derive(Debug)]
+pub enum Claim {
+    /// The key is new, or had expired, and is now held for this request.
+    New,
+    /// The key is held by an earlier request.
+    Existing(IdempotentRequest),
+}
+
+/// Concrete idempotency key related database logic
+pub struct IdempotencyRepo {
+    db: Arc<PgPool>,
+}
+
+impl IdempotencyRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl IdempotencyRepo {
+    /// Claim a client's key for a request, unless an earlier request holds it and has not expired.
+    /// Keys held by requests still in progress after `stale_after` are treated as abandoned.
+    #[instrument(skip(self, request_hash))]
+    pub async fn claim(
+        &self,
+        client: &str,
+        route: &str,
+        key: &str,
+        request_hash: &[u8],
+        ttl: Duration,
+        stale_after: Duration,
+    ) -> Result<Claim> {
+        tracing::debug!("claim: {} {} {}", client, route, key);
+
+        let claim_sql = r#"
+            INSERT INTO idempotency_keys (client, route, key, request_hash, expires_at)
+            VALUES ($1, $2, $3, $4, now() + make_interval(secs => $5))
+            ON CONFLICT (client, route, key) DO UPDATE
+            SET request_hash = EXCLUDED.request_hash,
+                status = NULL,
+                content_type = NULL,
+                response = NULL,
+                created_at = now(),
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at < now()
+            OR (
+                idempotency_keys.status IS NULL
+                AND idempotency_keys.created_at < now() - make_interval(secs => $6)
+            )
+            RETURNING key
+        "#;
+
+        let claimed: Option<String> = sqlx::query_scalar(claim_sql)
+            .bind(client)
+            .bind(route)
+            .bind(key)
+            .bind(request_hash)
+            .bind(ttl.as_secs_f64())
+            .bind(stale_after.as_secs_f64())
+            .fetch_optional(self.db_ref())
+            .await?;
+
+        if claimed.is_some() {
+            return Ok(Claim::New);
+        }
+
+        let existing_sql = r#"
+            SELECT request_hash, status, content_type, response
+            FROM idempotency_keys
+            WHERE client = $1 AND route = $2 AND key = $3
+        "#;
+
+        let existing = sqlx::query_as(existing_sql)
+            .bind(client)
+            .bind(route)
+            .bind(key)
+            .fetch_one(self.db_ref())
+            .await?;
+
+        Ok(Claim::Existing(existing))
+    }
+
+    /// Store the response for a claimed key, so retries can replay it.
+    #[instrument(skip(self, response))]
+    pub async fn complete(
+        &self,
+        client: &str,
+        route: &str,
+        key: &str,
+        status: u16,
+        content_type: Option<&str>,
+        response: &[u8],
+    ) -> Result<()> {
+        tracing::debug!("complete: {} {} {} {}", client, route, key, status);
+
+        let sql = r#"
+            UPDATE idempotency_keys
+            SET status = $4, content_type = $5, response = $6
+            WHERE client = $1 AND route = $2 AND key = $3
+        "#;
+
+        sqlx::query(sql)
+            .bind(client)
+            .bind(route)
+            .bind(key)
+            .bind(status as i16)
+            .bind(content_type)
+            .bind(response)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Release a claimed key without a response, so the request can be retried.
+    #[instrument(skip(self))]
+    pub async fn release(&self, client: &str, route: &str, key: &str) -> Result<()> {
+        tracing::debug!("release: {} {} {}", client, route, key);
+
+        let sql = r#"
+            DELETE FROM idempotency_keys
+            WHERE client = $1 AND route = $2 AND key = $3 AND status IS NULL
+        "#;
+
+        sqlx::query(sql)
+            .bind(client)
+            .bind(route)
+            .bind(key)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete expired keys, returning how many were deleted.
+    #[instrument(skip(self))]
+    pub async fn purge_expired(&self) -> Result<u64> {
+        tracing::debug!("purge_expired");
+
+        let sql = r#"
+            DELETE FROM idempotency_keys
+            WHERE expires_at < now()
+        "#;
+
+        let result = sqlx::query(sql).execute(self.db_ref()).await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::tests;
+    use std::sync::Arc;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    /// The client claiming keys
+    const CLIENT: &str = "principal:CN=gsd-cli";
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+
+        // Set up repo under test
+        let repo = IdempotencyRepo::new(Arc::clone(&pool));
+        let ttl = Duration::from_secs(60);
+
+        // First claim holds the key
+        let claim = repo
+            .claim(CLIENT, "/stories", "key-1", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+
+        // Retries see the request in progress, then its response
+        let claim = repo
+            .claim(CLIENT, "/stories", "key-1", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(
+            claim,
+            Claim::Existing(IdempotentRequest { status: None, .. })
+        ));
+        repo.complete(
+            CLIENT,
+            "/stories",
+            "key-1",
+            201,
+            Some("application/json"),
+            b"{}",
+        )
+        .await
+        .unwrap();
+        let Claim::Existing(existing) = repo
+            .claim(CLIENT, "/stories", "key-1", b"other", ttl, ttl)
+            .await
+            .unwrap()
+        else {
+            panic!("expected an existing claim");
+        };
+        assert_eq!(existing.request_hash, b"hash");
+        assert_eq!(existing.status, Some(201));
+        assert_eq!(existing.response.as_deref(), Some(b"{}".as_slice()));
+
+        // Keys are scoped by client and route
+        let claim = repo
+            .claim("ip:192.0.2.2", "/stories", "key-1", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+        let claim = repo
+            .claim(CLIENT, "/tasks", "key-1", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+
+        // Abandoned keys can be claimed again
+        repo.claim(CLIENT, "/tasks", "key-4", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        let claim = repo
+            .claim(CLIENT, "/tasks", "key-4", b"hash", ttl, Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+
+        // Released keys can be claimed again
+        repo.release(CLIENT, "/tasks", "key-1").await.unwrap();
+        let claim = repo
+            .claim(CLIENT, "/tasks", "key-1", b"hash", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+
+        // Expired keys are reclaimed, then purged
+        let claim = repo
+            .claim(CLIENT, "/stories", "key-2", b"hash", Duration::ZERO, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+        let claim = repo
+            .claim(CLIENT, "/stories", "key-2", b"other", ttl, ttl)
+            .await
+            .unwrap();
+        assert!(matches!(claim, Claim::New));
+        repo.claim(CLIENT, "/stories", "key-3", b"hash", Duration::ZERO, ttl)
+            .await
+            .unwrap();
+        assert_eq!(repo.purge_expired().await.unwrap(), 1);
+    }
+}
@@ -1,21 +1,122 @@
-use crate::Error;
+use crate::{Error, Result};
+use sqlx::{error::ErrorKind, migrate::Migrator, PgPool, Postgres, Transaction};
+use std::{future::Future, time::Duration};
 
+mod admin;
+mod export;
+mod idempotency;
+mod owner;
+mod recurrence;
+mod reminder;
+mod replica;
 mod story;
 mod task;
 
+pub use admin::{AdminRepo, MigrationState, MigrationStatus, OwnerBackfill, PoolStats};
+pub use export::ExportRepo;
+pub use idempotency::{Claim, IdempotencyRepo, IdempotentRequest};
+pub use owner::OwnerRepo;
+pub use recurrence::RecurrenceRepo;
+pub use reminder::ReminderRepo;
+pub use replica::{read_primary, Replica};
 pub use story::StoryRepo;
 pub use task::TaskRepo;
 
+/// Database migrations embedded into the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Attempts made at an operation that fails to serialize with concurrent transactions.
+const SERIALIZATION_ATTEMPTS: u32 = 3;
+
+/// Postgres error codes for transactions that can be retried.
+const SERIALIZATION_CODES: [&str; 2] = [
+    "40001", // serialization_failure
+    "40P01", // deadlock_detected
+];
+
+/// Message for unique violations, so constraint details aren't returned to clients.
+const CONFLICT_MESSAGE: &str = "conflicts with an existing record";
+
+/// Postgres error code classes and codes for a database that is down or not accepting connections.
+const UNAVAILABLE_CODES: [&str; 4] = [
+    "08",    // connection_exception
+    "53300", // too_many_connections
+    "57P01", // admin_shutdown
+    "57P03", // cannot_connect_now
+];
+
+/// Classify database errors, so outages and conflicts aren't reported as internal errors.
 impl From<sqlx::Error> for Error {
     fn from(err: sqlx::Error) -> Self {
-        Error::Internal {
-            message: err.to_string(),
+        let message = err.to_string();
+        if is_unavailable(&err) {
+            return Error::Unavailable { message };
+        }
+        match err {
+            sqlx::Error::Database(db_err) => {
+                let code = db_err.code().unwrap_or_default();
+                if SERIALIZATION_CODES.contains(&code.as_ref()) {
+                    Error::SerializationFailure { message }
+                } else if db_err.kind() == ErrorKind::UniqueViolation {
+                    tracing::debug!("unique violation: {}", message);
+                    Error::Conflict {
+                        message: CONFLICT_MESSAGE.into(),
+                    }
+                } else {
+                    Error::Internal { message }
+                }
+            }
+            _ => Error::Internal { message },
+        }
+    }
+}
+
+/// Whether an error means the database can't be reached right now.
+pub(crate) fn is_unavailable(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) => true,
+        sqlx::Error::Database(db_err) => db_err.code().is_some_and(|code| {
+            UNAVAILABLE_CODES
+                .iter()
+                .any(|prefix| code.starts_with(prefix))
+        }),
+        _ => false,
+    }
+}
+
+/// Begin a serializable transaction, for operations that read and write several rows and so can
+/// fail to serialize with concurrent transactions.
+async fn begin_serializable(db: &PgPool) -> Result<Transaction<'static, Postgres>> {
+    let mut transaction = db.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+        .execute(&mut *transaction)
+        .await?;
+    Ok(transaction)
+}
+
+/// Run a database operation, retrying it with backoff when it fails to serialize or deadlocks.
+async fn retry_serialization<T, F, Fut>(mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(Error::SerializationFailure { message }) if attempt < SERIALIZATION_ATTEMPTS => {
+                tracing::warn!(attempt, "retrying after serialization failure: {}", message);
+                metrics::counter!("gsd_db_retries_total").increment(1);
+                tokio::time::sleep(Duration::from_millis(20 << attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
         }
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use super::{retry_serialization, Error, SERIALIZATION_ATTEMPTS};
     use sqlx::{
         migrate::Migrator,
         postgres::{PgPool, PgPoolOptions},
@@ -35,14 +136,60 @@ mod tests {
         let pool = PgPoolOptions::new()
             .max_connections(2)
             .min_connections(1)
-            .connect(&connection_string)
+            .connect(connection_string)
             .await
             .unwrap();
 
-        log::debug!("Running migrations on test container");
+        tracing::debug!("Running migrations on test container");
         let m = Migrator::new(Path::new("./migrations")).await.unwrap();
         m.run(&pool).await.unwrap();
 
         Arc::new(pool)
     }
+
+    #[test]
+    fn classify_errors() {
+        let err = Error::from(sqlx::Error::PoolTimedOut);
+        assert!(matches!(err, Error::Unavailable { .. }));
+
+        let io = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let err = Error::from(sqlx::Error::Io(io));
+        assert!(matches!(err, Error::Unavailable { .. }));
+
+        let err = Error::from(sqlx::Error::RowNotFound);
+        assert!(matches!(err, Error::Internal { .. }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_serialization_failures() {
+        let mut calls = 0;
+        let result = retry_serialization(|| {
+            calls += 1;
+            let calls = calls;
+            async move {
+                if calls < SERIALIZATION_ATTEMPTS {
+                    Err(Error::SerializationFailure {
+                        message: "could not serialize access".into(),
+                    })
+                } else {
+                    Ok(calls)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), SERIALIZATION_ATTEMPTS);
+
+        let mut calls = 0;
+        let result: crate::Result<()> = retry_serialization(|| {
+            calls += 1;
+            async {
+                Err(Error::SerializationFailure {
+                    message: "could not serialize access".into(),
+                })
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::SerializationFailure { .. })));
+        assert_eq!(calls, SERIALIZATION_ATTEMPTS);
+    }
 }
@@ -0,0 +1,131 @@
+use crate::{domain::OwnerSettings, Result};
+use chrono_tz::Tz;
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    FromRow, Row,
+};
+use std::sync::Arc;
+use tracing::instrument;
+
+/// Map sqlx rows to owner settings domain objects.
+impl FromRow<'_, PgRow> for OwnerSettings {
+    fn from_row(row: &PgRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            owner: row.try_get("owner")?,
+            timezone: timezone(row)?,
+            email: row.try_get("email")?,
+        })
+    }
+}
+
+/// Get an owner's time zone from a row, which is UTC when the owner has no settings.
+pub(crate) fn timezone(row: &PgRow) -> std::result::Result<Tz, sqlx::Error> {
+    let timezone: Option<String> = row.try_get("timezone")?;
+    match timezone {
+        Some(timezone) => timezone
+            .parse()
+            .map_err(|err: chrono_tz::ParseError| sqlx::Error::Decode(Box::new(err))),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// Concrete owner settings related database logic
+pub struct OwnerRepo {
+    db: Arc<PgPool>,
+}
+
+impl OwnerRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl OwnerRepo {
+    /// Get the settings for an owner, or the defaults when none are saved.
+    #[instrument(skip(self))]
+    pub async fn fetch(&self, owner: &str) -> Result<OwnerSettings> {
+        tracing::debug!("select_owner: {}", owner);
+
+        let sql = r#"
+            SELECT owner, timezone, email
+            FROM owners
+            WHERE owner = $1
+        "#;
+
+        let settings = sqlx::query_as(sql)
+            .bind(owner)
+            .fetch_optional(self.db_ref())
+            .await?;
+
+        Ok(settings.unwrap_or_else(|| OwnerSettings::new(owner.to_owned())))
+    }
+
+    /// Set or replace the settings for an owner
+    #[instrument(skip(self))]
+    pub async fn save(&self, settings: OwnerSettings) -> Result<OwnerSettings> {
+        tracing::debug!("upsert_owner: {:?}", settings);
+
+        let sql = r#"
+            INSERT INTO owners (owner, timezone, email)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (owner) DO UPDATE
+            SET timezone = EXCLUDED.timezone, email = EXCLUDED.email, updated_at = now()
+            RETURNING owner, timezone, email
+        "#;
+
+        let settings = sqlx::query_as(sql)
+            .bind(settings.owner)
+            .bind(settings.timezone.name())
+            .bind(settings.email)
+            .fetch_one(self.db_ref())
+            .await?;
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::tests;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+
+        // Set up repo under test
+        let repo = OwnerRepo::new(Arc::clone(&pool));
+
+        // Owners without settings get the defaults
+        let settings = repo.fetch("alice").await.unwrap();
+        assert_eq!(settings, OwnerSettings::new("alice".into()));
+
+        // Save, then replace settings
+        let settings = OwnerSettings {
+            timezone: Tz::Europe__Dublin,
+            email: Some("alice@example.com".into()),
+            ..settings
+        };
+        assert_eq!(repo.save(settings.clone()).await.unwrap(), settings);
+        let settings = OwnerSettings {
+            email: None,
+            ..settings
+        };
+        repo.save(settings.clone()).await.unwrap();
+        assert_eq!(repo.fetch("alice").await.unwrap(), settings);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    domain::{Recurrence, Task, TaskRecurrence},
+    repo::owner::timezone,
+    Error, Result,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::{
+    postgres::{PgConnection, PgPool, PgRow},
+    FromRow, Row,
+};
+use std::sync::Arc;
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Most recurring tasks created in one pass of the scheduler
+const DUE_BATCH_SIZE: i64 = 100;
+
+/// Map sqlx rows to task recurrence domain objects.
+impl FromRow<'_, PgRow> for TaskRecurrence {
+    fn from_row(row: &PgRow) -> std::result::Result<Self, sqlx::Error> {
+        let rule: String = row.try_get("rule")?;
+        let rule = rule
+            .parse()
+            .map_err(|err: Error| sqlx::Error::Decode(Box::new(err)))?;
+        Ok(Self {
+            task_id: row.try_get("task_id")?,
+            rule,
+            next_at: row.try_get("next_at")?,
+        })
+    }
+}
+
+/// Concrete task recurrence related database logic
+pub struct RecurrenceRepo {
+    db: Arc<PgPool>,
+}
+
+impl RecurrenceRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl RecurrenceRepo {
+    /// Get the recurrence rule for a task
+    #[instrument(skip(self))]
+    pub async fn fetch(&self, task_id: Uuid) -> Result<TaskRecurrence> {
+        tracing::debug!("select_recurrence: {}", task_id);
+
+        let sql = r#"
+            SELECT task_id, rule, next_at
+            FROM task_recurrences
+            WHERE task_id = $1
+        "#;
+
+        let recurrence = sqlx::query_as(sql)
+            .bind(task_id)
+            .fetch_optional(self.db_ref())
+            .await?;
+
+        recurrence.ok_or_else(|| Error::NotFound {
+            message: format!("task recurrence not found: {}", task_id),
+        })
+    }
+
+    /// Set or replace the recurrence rule for a task
+    #[instrument(skip(self))]
+    pub async fn save(
+        &self,
+        task_id: Uuid,
+        rule: Recurrence,
+        next_at: DateTime<Utc>,
+    ) -> Result<TaskRecurrence> {
+        tracing::debug!("upsert_recurrence: {}, {}, {}", task_id, rule, next_at);
+
+        let sql = r#"
+            INSERT INTO task_recurrences (task_id, rule, next_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (task_id) DO UPDATE
+            SET rule = EXCLUDED.rule, next_at = EXCLUDED.next_at, updated_at = now()
+            RETURNING task_id, rule, next_at
+        "#;
+
+        let recurrence = sqlx::query_as(sql)
+            .bind(task_id)
+            .bind(rule.to_string())
+            .bind(next_at)
+            .fetch_one(self.db_ref())
+            .await?;
+
+        Ok(recurrence)
+    }
+
+    /// Stop a task recurring
+    #[instrument(skip(self))]
+    pub async fn delete(&self, task_id: Uuid) -> Result<u64> {
+        tracing::debug!("delete_recurrence: {}", task_id);
+
+        let sql = "DELETE FROM task_recurrences WHERE task_id = $1";
+        let result = sqlx::query(sql)
+            .bind(task_id)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Skip the next occurrence of a recurring task
+    #[instrument(skip(self))]
+    pub async fn skip(&self, task_id: Uuid, now: DateTime<Utc>) -> Result<TaskRecurrence> {
+        tracing::debug!("skip_recurrence: {}", task_id);
+
+        let mut transaction = self.db.begin().await?;
+
+        let select_sql = r#"
+            SELECT r.task_id, r.rule, r.next_at, o.timezone
+            FROM task_recurrences r
+            JOIN tasks t ON t.id = r.task_id
+            JOIN stories s ON s.id = t.story_id
+            LEFT JOIN owners o ON o.owner = s.owner
+            WHERE r.task_id = $1
+            FOR UPDATE OF r
+        "#;
+
+        let Some(row) = sqlx::query(select_sql)
+            .bind(task_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+        else {
+            return Err(Error::NotFound {
+                message: format!("task recurrence not found: {}", task_id),
+            });
+        };
+        let recurrence = TaskRecurrence::from_row(&row)?;
+        let next_at = following(&recurrence, timezone(&row)?, now)?;
+
+        let update_sql = r#"
+            UPDATE task_recurrences
+            SET next_at = $2, updated_at = now()
+            WHERE task_id = $1
+            RETURNING task_id, rule, next_at
+        "#;
+
+        let recurrence = sqlx::query_as(update_sql)
+            .bind(task_id)
+            .bind(next_at)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(recurrence)
+    }
+
+    /// Create next occurrences for recurring tasks that are due.
+    #[instrument(skip(self))]
+    pub async fn create_due(&self, now: DateTime<Utc>) -> Result<Vec<Task>> {
+        tracing::debug!("create_due_occurrences: {}", now);
+
+        let sql = r#"
+            SELECT r.task_id
+            FROM task_recurrences r
+            JOIN tasks t ON t.id = r.task_id
+            WHERE r.next_at <= $1 AND t.deleted_at IS NULL
+            ORDER BY r.next_at ASC
+            LIMIT $2
+        "#;
+
+        let task_ids: Vec<Uuid> = sqlx::query_scalar(sql)
+            .bind(now)
+            .bind(DUE_BATCH_SIZE)
+            .fetch_all(self.db_ref())
+            .await?;
+
+        let mut tasks = Vec::with_capacity(task_ids.len());
+        for task_id in task_ids {
+            // Another server may have got there first
+            let mut transaction = self.db.begin().await?;
+            if let Some(task) = occur(&mut transaction, task_id, now, true).await? {
+                transaction.commit().await?;
+                tasks.push(task);
+            }
+        }
+
+        Ok(tasks)
+    }
+}
+
+/// Create the next occurrence of a recurring task in the same story, moving the recurrence to it,
+/// within the caller's transaction. The recurrence is held so concurrent completions and schedulers
+/// don't create duplicates. When the task has a due date, the occurrence is due when the one after
+/// it is. Returns `None` when the task is not the latest occurrence of a recurring task, or when
+/// `due_only` is set and it isn't due yet.
+pub(super) async fn occur(
+    connection: &mut PgConnection,
+    task_id: Uuid,
+    now: DateTime<Utc>,
+    due_only: bool,
+) -> Result<Option<Task>> {
+    let select_sql = r#"
+        SELECT r.task_id, r.rule, r.next_at, t.story_id, t.name, t.due_at, o.timezone
+        FROM task_recurrences r
+        JOIN tasks t ON t.id = r.task_id
+        JOIN stories s ON s.id = t.story_id
+        LEFT JOIN owners o ON o.owner = s.owner
+        WHERE r.task_id = $1 AND t.deleted_at IS NULL AND (NOT $3 OR r.next_at <= $2)
+        FOR UPDATE OF r
+    "#;
+
+    let Some(row) = sqlx::query(select_sql)
+        .bind(task_id)
+        .bind(now)
+        .bind(due_only)
+        .fetch_optional(&mut *connection)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let recurrence = TaskRecurrence::from_row(&row)?;
+    let story_id: Uuid = row.try_get("story_id")?;
+    let name: String = row.try_get("name")?;
+    let due_at: Option<DateTime<Utc>> = row.try_get("due_at")?;
+    let next_at = following(&recurrence, timezone(&row)?, now)?;
+
+    let insert_sql = r#"
+        INSERT INTO tasks (story_id, name, due_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, story_id, name, status, due_at
+    "#;
+
+    let task: Task = sqlx::query_as(insert_sql)
+        .bind(story_id)
+        .bind(name)
+        .bind(due_at.map(|_| next_at))
+        .fetch_one(&mut *connection)
+        .await?;
+
+    let update_sql = r#"
+        UPDATE task_recurrences
+        SET task_id = $2, next_at = $3, updated_at = now()
+        WHERE task_id = $1
+    "#;
+
+    sqlx::query(update_sql)
+        .bind(task_id)
+        .bind(task.id)
+        .bind(next_at)
+        .execute(&mut *connection)
+        .await?;
+
+    Ok(Some(task))
+}
+
+/// When the occurrence after the scheduled one is due, by the owner's clock.
+fn following(recurrence: &TaskRecurrence, tz: Tz, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    recurrence
+        .rule
+        .following(
+            &recurrence.next_at.with_timezone(&tz),
+            &now.with_timezone(&tz),
+        )
+        .map(|next| next.with_timezone(&Utc))
+        .ok_or_else(|| Error::Internal {
+            message: format!("no next occurrence for rule: {}", recurrence.rule),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::Status,
+        repo::{tests, StoryRepo, TaskRepo},
+    };
+    use chrono::Duration;
+    use std::sync::Arc;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+        let story_repo = StoryRepo::new(Arc::clone(&pool));
+        let task_repo = TaskRepo::new(Arc::clone(&pool));
+
+        // Set up repo under test
+        let repo = RecurrenceRepo::new(Arc::clone(&pool));
+
+        let story = story_repo
+            .create("Ops".into(), "recurrence".into())
+            .await
+            .unwrap();
+        let now = Utc::now();
+        let next_at = Recurrence::Daily.next_after(&now).unwrap();
+        let task = task_repo
+            .create(story.id, "Rotate logs".into(), Some(next_at))
+            .await
+            .unwrap();
+        repo.save(task.id, Recurrence::Daily, next_at)
+            .await
+            .unwrap();
+
+        // Skipping moves the next occurrence a day on
+        let skipped = repo.skip(task.id, now).await.unwrap();
+        assert_eq!(skipped.next_at, next_at + Duration::days(1));
+
+        // Completing creates the next occurrence, which takes over the recurrence
+        task_repo
+            .update(task.id, task.name.clone(), Status::Complete, task.due_at)
+            .await
+            .unwrap();
+        let next = task_repo.fetch_all(story.id).await.unwrap().pop().unwrap();
+        assert_ne!(next.id, task.id);
+        assert_eq!((next.story_id, next.status), (story.id, Status::Incomplete));
+        assert_eq!(next.due_at, Some(next_at + Duration::days(2)));
+
+        // Completing it again doesn't recur twice
+        task_repo
+            .update(task.id, task.name.clone(), Status::Complete, task.due_at)
+            .await
+            .unwrap();
+        assert_eq!(task_repo.fetch_all(story.id).await.unwrap().len(), 2);
+        let recurrence = repo.fetch(next.id).await.unwrap();
+        assert_eq!(recurrence.next_at, next_at + Duration::days(2));
+
+        // Nothing is due until the next occurrence
+        assert!(repo.create_due(now).await.unwrap().is_empty());
+        let later = recurrence.next_at;
+        let due = repo.create_due(later).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(task_repo.fetch_all(story.id).await.unwrap().len(), 3);
+    }
+}
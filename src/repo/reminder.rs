@@ -0,0 +1,248 @@
+use crate::{domain::Reminder, repo::owner::timezone, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    FromRow, Row,
+};
+use std::{sync::Arc, time::Duration};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Most reminders claimed in one pass of the scheduler
+const DUE_BATCH_SIZE: i64 = 100;
+
+/// How long a claimed reminder is held before it can be claimed again, unless it is marked sent
+/// or released first. Long enough for a scheduler to send a full batch.
+const CLAIM_LEASE: Duration = Duration::from_secs(15 * 60);
+
+/// Map sqlx rows to reminder domain objects.
+impl FromRow<'_, PgRow> for Reminder {
+    fn from_row(row: &PgRow) -> std::result::Result<Self, sqlx::Error> {
+        Ok(Self {
+            task_id: row.try_get("task_id")?,
+            task_name: row.try_get("task_name")?,
+            story_id: row.try_get("story_id")?,
+            story_name: row.try_get("story_name")?,
+            owner: row.try_get("owner")?,
+            email: row.try_get("email")?,
+            timezone: timezone(row)?,
+            due_at: row.try_get("due_at")?,
+        })
+    }
+}
+
+/// Concrete due date reminder related database logic
+pub struct ReminderRepo {
+    db: Arc<PgPool>,
+}
+
+impl ReminderRepo {
+    /// Constructor
+    pub fn new(db: Arc<PgPool>) -> Self {
+        Self { db }
+    }
+
+    /// Get a ref to the connection pool.
+    fn db_ref(&self) -> &PgPool {
+        self.db.as_ref()
+    }
+}
+
+impl ReminderRepo {
+    /// Claim unsent reminders for incomplete tasks due before a time, leasing them so concurrent
+    /// schedulers don't claim them too. Reminders whose lease expired, because the scheduler
+    /// holding them stopped before marking them sent, are claimed again.
+    #[instrument(skip(self))]
+    pub async fn claim_due(&self, due_before: DateTime<Utc>) -> Result<Vec<Reminder>> {
+        tracing::debug!("claim_reminders: {}", due_before);
+
+        let sql = r#"
+            WITH due AS (
+                SELECT t.id, t.story_id
+                FROM tasks t
+                JOIN stories s ON s.id = t.story_id
+                WHERE t.due_at <= $1 AND t.reminded_at IS NULL AND t.status = 'incomplete'
+                AND t.deleted_at IS NULL AND s.deleted_at IS NULL
+                AND (t.reminder_claimed_at IS NULL
+                    OR t.reminder_claimed_at < now() - make_interval(secs => $3))
+                ORDER BY t.due_at ASC
+                LIMIT $2
+                FOR UPDATE OF t SKIP LOCKED
+            )
+            UPDATE tasks t
+            SET reminder_claimed_at = now()
+            FROM due
+            JOIN stories s ON s.id = due.story_id
+            LEFT JOIN owners o ON o.owner = s.owner
+            WHERE t.id = due.id
+            RETURNING t.id AS task_id, t.name AS task_name, s.id AS story_id,
+                s.name AS story_name, s.owner, o.email, o.timezone, t.due_at
+        "#;
+
+        let reminders = sqlx::query_as(sql)
+            .bind(due_before)
+            .bind(DUE_BATCH_SIZE)
+            .bind(CLAIM_LEASE.as_secs_f64())
+            .fetch_all(self.db_ref())
+            .await?;
+
+        Ok(reminders)
+    }
+
+    /// Mark a claimed reminder sent, so it isn't claimed again unless the due date it was sent
+    /// for changes.
+    #[instrument(skip(self))]
+    pub async fn mark_sent(&self, task_id: Uuid, due_at: DateTime<Utc>) -> Result<u64> {
+        tracing::debug!("mark_reminder_sent: {}, {}", task_id, due_at);
+
+        let sql = r#"
+            UPDATE tasks SET reminded_at = now(), reminder_claimed_at = NULL
+            WHERE id = $1 AND due_at = $2
+        "#;
+        let result = sqlx::query(sql)
+            .bind(task_id)
+            .bind(due_at)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Release a claimed reminder that couldn't be sent, so it is claimed again.
+    #[instrument(skip(self))]
+    pub async fn release(&self, task_id: Uuid) -> Result<u64> {
+        tracing::debug!("release_reminder: {}", task_id);
+
+        let sql = "UPDATE tasks SET reminder_claimed_at = NULL WHERE id = $1";
+        let result = sqlx::query(sql)
+            .bind(task_id)
+            .execute(self.db_ref())
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{OwnerSettings, Status},
+        repo::{tests, OwnerRepo, StoryRepo, TaskRepo},
+    };
+    use chrono::Duration;
+    use chrono_tz::Tz;
+
+    use testcontainers::{clients::Cli, RunnableImage};
+    use testcontainers_modules::postgres::Postgres;
+
+    #[ignore]
+    #[tokio::test]
+    async fn integration_test() {
+        // Set up postgres test container backed repo
+        let docker = Cli::default();
+        let image = RunnableImage::from(Postgres::default()).with_tag("16-alpine");
+        let container = docker.run(image);
+        let pool = tests::setup_pg_pool(&container).await;
+        let story_repo = StoryRepo::new(Arc::clone(&pool));
+        let task_repo = TaskRepo::new(Arc::clone(&pool));
+        let owner_repo = OwnerRepo::new(Arc::clone(&pool));
+
+        // Set up repo under test
+        let repo = ReminderRepo::new(Arc::clone(&pool));
+
+        let settings = OwnerSettings {
+            timezone: Tz::Asia__Tokyo,
+            email: Some("alice@example.com".into()),
+            ..OwnerSettings::new("alice".into())
+        };
+        owner_repo.save(settings).await.unwrap();
+        let story = story_repo
+            .create("Chores".into(), "alice".into())
+            .await
+            .unwrap();
+        let now = Utc::now();
+        let soon = task_repo
+            .create(story.id, "Soon".into(), Some(now + Duration::minutes(30)))
+            .await
+            .unwrap();
+        task_repo
+            .create(story.id, "Later".into(), Some(now + Duration::days(2)))
+            .await
+            .unwrap();
+        task_repo
+            .create(story.id, "Someday".into(), None)
+            .await
+            .unwrap();
+
+        // Only tasks due within the window are claimed, and not again while leased
+        let reminders = repo.claim_due(now + Duration::hours(1)).await.unwrap();
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].task_id, soon.id);
+        assert_eq!(reminders[0].timezone, Tz::Asia__Tokyo);
+        assert_eq!(reminders[0].email.as_deref(), Some("alice@example.com"));
+        assert!(repo
+            .claim_due(now + Duration::hours(1))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Reminders are claimed again once their lease expires, as when a scheduler stops
+        // before sending them
+        let expire_sql = "UPDATE tasks SET reminder_claimed_at = now() - interval '1 day'";
+        sqlx::query(expire_sql)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.claim_due(now + Duration::hours(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Released reminders are claimed again
+        repo.release(soon.id).await.unwrap();
+        let reminders = repo.claim_due(now + Duration::hours(1)).await.unwrap();
+        assert_eq!(reminders.len(), 1);
+
+        // Sent reminders aren't claimed again
+        let sent = repo.mark_sent(soon.id, reminders[0].due_at).await.unwrap();
+        assert_eq!(sent, 1);
+        sqlx::query(expire_sql)
+            .execute(pool.as_ref())
+            .await
+            .unwrap();
+        assert!(repo
+            .claim_due(now + Duration::hours(1))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Moving the due date means a new reminder
+        let due_at = now + Duration::minutes(45);
+        task_repo
+            .update(soon.id, soon.name.clone(), Status::Incomplete, Some(due_at))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.claim_due(now + Duration::hours(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Completed tasks aren't reminded
+        task_repo
+            .update(soon.id, soon.name, Status::Complete, None)
+            .await
+            .unwrap();
+        assert!(repo
+            .claim_due(now + Duration::days(1))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use sqlx::postgres::PgPool;
+use std::{
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+tokio::task_local! {
+    static READ_PRIMARY: bool;
+}
+
+/// Run a future with reads routed to the primary, so it sees writes the replica hasn't caught up on.
+pub async fn read_primary<F: Future>(f: F) -> F::Output {
+    READ_PRIMARY.scope(true, f).await
+}
+
+/// A read replica, which reads fall back from to the primary while it is unavailable.
+pub struct Replica {
+    pool: PgPool,
+    available: AtomicBool,
+}
+
+impl Replica {
+    /// A replica that is unavailable until a check says otherwise.
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            available: AtomicBool::new(false),
+        }
+    }
+
+    /// Get a ref to the connection pool.
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Whether reads are sent to the replica.
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    /// Record the outcome of a check, logging when reads move between replica and primary.
+    pub(crate) fn set_available(&self, available: bool) {
+        if self.available.swap(available, Ordering::Relaxed) != available {
+            if available {
+                tracing::info!("Replica available, reading from replica");
+            } else {
+                tracing::warn!("Replica unavailable, reading from primary");
+            }
+        }
+    }
+}
+
+/// Choose the pool for a read: the replica when there is one and it is available, unless reading
+/// from the primary.
+pub(crate) fn reader<'a>(primary: &'a PgPool, replica: Option<&'a Replica>) -> &'a PgPool {
+    let read_primary = READ_PRIMARY.try_with(|p| *p).unwrap_or(false);
+    match replica {
+        Some(replica) if !read_primary && replica.is_available() => replica.pool(),
+        _ => primary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn reads_use_replica_unless_reading_primary() {
+        let primary = PgPoolOptions::new()
+            .connect_lazy("postgres://primary/gsd")
+            .unwrap();
+        let replica = PgPoolOptions::new()
+            .connect_lazy("postgres://replica/gsd")
+            .unwrap();
+        let replica = Replica::new(replica);
+        let host = |pool: &PgPool| pool.connect_options().get_host().to_owned();
+
+        assert_eq!(host(reader(&primary, None)), "primary");
+
+        // Reads fall back to the primary until the replica is available
+        assert_eq!(host(reader(&primary, Some(&replica))), "primary");
+        replica.set_available(true);
+        assert_eq!(host(reader(&primary, Some(&replica))), "replica");
+        let pool = read_primary(async { host(reader(&primary, Some(&replica))) }).await;
+        assert_eq!(pool, "primary");
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{domain::Story, Error, Result};
-use futures_util::TryStreamExt;
+use crate::{
+    domain::Story,
+    repo::{begin_serializable, replica, retry_serialization, Replica},
+    Error, Result,
+};
+use futures_util::{TryFutureExt, TryStreamExt};
 use sqlx::{
     postgres::{PgPool, PgRow},
     FromRow, Row,
 };
 use std::sync::Arc;
+use tracing::instrument;
 use uuid::Uuid;
 
 /// Map sqlx rows to story domain objects.
@@ -21,24 +26,36 @@ impl FromRow<'_, PgRow> for Story {
 /// Concrete story related database logic
 pub struct StoryRepo {
     db: Arc<PgPool>,
+    replica: Option<Arc<Replica>>,
 }
 
 impl StoryRepo {
     /// Constructor
     pub fn new(db: Arc<PgPool>) -> Self {
-        Self { db }
+        Self { db, replica: None }
+    }
+
+    /// Constructor that sends reads to a replica.
+    pub fn with_replica(db: Arc<PgPool>, replica: Option<Arc<Replica>>) -> Self {
+        Self { db, replica }
     }
 
     /// Get a ref to the connection pool.
     fn db_ref(&self) -> &PgPool {
         self.db.as_ref()
     }
+
+    /// Get a ref to the connection pool for reads.
+    fn read_ref(&self) -> &PgPool {
+        replica::reader(self.db_ref(), self.replica.as_deref())
+    }
 }
 
 impl StoryRepo {
     /// Select a story by id
+    #[instrument(skip(self))]
     pub async fn fetch(&self, id: Uuid) -> Result<Story> {
-        log::debug!("fetch: {}", id);
+        tracing::debug!("fetch: {}", id);
 
         let sql = r#"
             SELECT id, name, owner
@@ -49,7 +66,7 @@ impl StoryRepo {
 
         let maybe_story = sqlx::query_as(sql)
             .bind(id)
-            .fetch_optional(self.db_ref())
+            .fetch_optional(self.read_ref())
             .await?;
 
         match maybe_story {
@@ -61,8 +78,9 @@ impl StoryRepo {
     }
 
     /// Select stories for an owner
+    #[instrument(skip(self))]
     pub async fn fetch_all(&self, owner: String) -> Result<Vec<Story>> {
-        log::debug!("fetch_all: {}", owner);
+        tracing::debug!("fetch_all: {}", owner);
 
         let sql = r#"
             SELECT id, name, owner
@@ -71,7 +89,7 @@ impl StoryRepo {
             ORDER BY created_at ASC
         "#;
 
-        let mut result_set = sqlx::query(sql).bind(owner).fetch(self.db_ref());
+        let mut result_set = sqlx::query(sql).bind(owner).fetch(self.read_ref());
         let mut result = Vec::new();
 
         while let Some(row) = result_set.try_next().await? {
@@ -83,8 +101,9 @@ impl StoryRepo {
     }
 
     /// Insert a new story
+    #[instrument(skip(self))]
     pub async fn create(&self, name: String, owner: String) -> Result<Story> {
-        log::debug!("create: {}, {}", name, owner);
+        tracing::debug!("create: {}, {}", name, owner);
 
         let sql = r#"
             INSERT INTO stories (name, owner)
@@ -92,18 +111,20 @@ impl StoryRepo {
             RETURNING id, name, owner
         "#;
 
-        let story = sqlx::query_as(sql)
-            .bind(name)
-            .bind(owner)
-            .fetch_one(self.db_ref())
-            .await?;
-
-        Ok(story)
+        retry_serialization(|| {
+            sqlx::query_as(sql)
+                .bind(name.clone())
+                .bind(owner.clone())
+                .fetch_one(self.db_ref())
+                .map_err(Error::from)
+        })
+        .await
     }
 
     /// Update story name and owner
+    #[instrument(skip(self))]
     pub async fn update(&self, id: Uuid, name: String, owner: String) -> Result<Story> {
-        log::debug!("update_story: {}, {}, {}", id, name, owner);
+        tracing::debug!("update_story: {}, {}, {}", id, name, owner);
 
         let sql = r#"
             UPDATE stories
@@ -112,21 +133,27 @@ impl StoryRepo {
             RETURNING id, name, owner
         "#;
 
-        let story = sqlx::query_as(sql)
-            .bind(name)
-            .bind(owner)
-            .bind(id)
-            .fetch_one(self.db_ref())
-            .await?;
-
-        Ok(story)
+        retry_serialization(|| {
+            sqlx::query_as(sql)
+                .bind(name.clone())
+                .bind(owner.clone())
+                .bind(id)
+                .fetch_one(self.db_ref())
+                .map_err(Error::from)
+        })
+        .await
     }
 
     /// Delete a story and its tasks by setting the deleted_at timestamp.
+    #[instrument(skip(self))]
     pub async fn delete(&self, id: Uuid) -> Result<u64> {
-        log::debug!("delete_story: {}", id);
+        tracing::debug!("delete_story: {}", id);
+        retry_serialization(|| self.try_delete(id)).await
+    }
 
-        let mut transaction = self.db.begin().await?;
+    /// Delete a story and its tasks in a single transaction.
+    async fn try_delete(&self, id: Uuid) -> Result<u64> {
+        let mut transaction = begin_serializable(self.db_ref()).await?;
 
         let delete_tasks_sql = r#"
             UPDATE tasks SET deleted_at = now()
@@ -1,16 +1,26 @@
 use crate::{
     domain::{Status, Task},
+    repo::{recurrence, replica, retry_serialization, Replica},
     Error, Result,
 };
-use futures_util::TryStreamExt;
+use chrono::{DateTime, Utc};
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
 use sqlx::{
     postgres::{PgPool, PgRow},
     FromRow, Row,
 };
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{instrument, Instrument};
 use uuid::Uuid;
 
+/// Most rows buffered ahead of a slow consumer when streaming tasks
+const STREAM_BUFFER: usize = 32;
+
 /// Map sqlx rows to task domain objects.
 impl FromRow<'_, PgRow> for Task {
     fn from_row(row: &PgRow) -> std::result::Result<Self, sqlx::Error> {
@@ -19,6 +29,7 @@ impl FromRow<'_, PgRow> for Task {
         let story_id = row.try_get("story_id")?;
         let name = row.try_get("name")?;
         let status: String = row.try_get("status")?;
+        let due_at = row.try_get("due_at")?;
 
         // Convert to enum type
         let status = Status::from_str(&status).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
@@ -29,6 +40,7 @@ impl FromRow<'_, PgRow> for Task {
             story_id,
             name,
             status,
+            due_at,
         })
     }
 }
@@ -36,27 +48,39 @@ impl FromRow<'_, PgRow> for Task {
 /// Concrete task related database logic
 pub struct TaskRepo {
     db: Arc<PgPool>,
+    replica: Option<Arc<Replica>>,
 }
 
 impl TaskRepo {
     /// Constructor
     pub fn new(db: Arc<PgPool>) -> Self {
-        Self { db }
+        Self { db, replica: None }
+    }
+
+    /// Constructor that sends reads to a replica.
+    pub fn with_replica(db: Arc<PgPool>, replica: Option<Arc<Replica>>) -> Self {
+        Self { db, replica }
     }
 
     /// Get a ref to the connection pool.
     fn db_ref(&self) -> &PgPool {
         self.db.as_ref()
     }
+
+    /// Get a ref to the connection pool for reads.
+    fn read_ref(&self) -> &PgPool {
+        replica::reader(self.db_ref(), self.replica.as_deref())
+    }
 }
 
 impl TaskRepo {
     /// Get a task by id
+    #[instrument(skip(self))]
     pub async fn fetch(&self, id: Uuid) -> Result<Task> {
-        log::debug!("select_task: {}", id);
+        tracing::debug!("select_task: {}", id);
 
         let sql = r#"
-            SELECT id, story_id, name, status
+            SELECT id, story_id, name, status, due_at
             FROM tasks
             WHERE id = $1
             AND deleted_at IS NULL
@@ -64,7 +88,7 @@ impl TaskRepo {
 
         let task_option = sqlx::query_as(sql)
             .bind(id)
-            .fetch_optional(self.db_ref())
+            .fetch_optional(self.read_ref())
             .await?;
 
         match task_option {
@@ -76,17 +100,18 @@ impl TaskRepo {
     }
 
     /// Select tasks for a story
+    #[instrument(skip(self))]
     pub async fn fetch_all(&self, story_id: Uuid) -> Result<Vec<Task>> {
-        log::debug!("select_tasks: story: {}", story_id);
+        tracing::debug!("select_tasks: story: {}", story_id);
 
         let sql = r#"
-            SELECT id, story_id, name, status
+            SELECT id, story_id, name, status, due_at
             FROM tasks
             WHERE story_id = $1 AND deleted_at IS NULL
             ORDER BY created_at ASC
         "#;
 
-        let mut result_set = sqlx::query(sql).bind(story_id).fetch(self.db_ref());
+        let mut result_set = sqlx::query(sql).bind(story_id).fetch(self.read_ref());
         let mut result = Vec::new();
 
         while let Some(row) = result_set.try_next().await? {
@@ -97,49 +122,193 @@ impl TaskRepo {
         Ok(result)
     }
 
-    /// Insert a new task
-    pub async fn create(&self, story_id: Uuid, name: String) -> Result<Task> {
-        log::debug!("insert_task: {}, {}", story_id, name);
+    /// Stream tasks for a story as rows arrive, buffering at most a few ahead of the consumer.
+    #[instrument(skip(self))]
+    pub fn stream_all(&self, story_id: Uuid) -> BoxStream<'static, Result<Task>> {
+        tracing::debug!("stream_tasks: story: {}", story_id);
+
+        let db = self.read_ref().clone();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let producer = async move {
+            let sql = r#"
+                SELECT id, story_id, name, status, due_at
+                FROM tasks
+                WHERE story_id = $1 AND deleted_at IS NULL
+                ORDER BY created_at ASC
+            "#;
+
+            let mut result_set = sqlx::query_as::<_, Task>(sql).bind(story_id).fetch(&db);
+            while let Some(task) = result_set.next().await {
+                // Stop reading once the consumer has gone away
+                if tx.send(task.map_err(Error::from)).await.is_err() {
+                    break;
+                }
+            }
+        };
+        tokio::spawn(producer.in_current_span());
+
+        stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|task| (task, rx))
+        })
+        .boxed()
+    }
+
+    /// Select tasks for a set of stories
+    #[instrument(skip(self))]
+    pub async fn fetch_for_stories(&self, story_ids: &[Uuid]) -> Result<Vec<Task>> {
+        tracing::debug!("select_tasks: stories: {:?}", story_ids);
 
         let sql = r#"
-            INSERT INTO tasks (story_id, name)
-            VALUES ($1, $2)
-            RETURNING id, story_id, name, status
+            SELECT id, story_id, name, status, due_at
+            FROM tasks
+            WHERE story_id = ANY($1) AND deleted_at IS NULL
+            ORDER BY created_at ASC
         "#;
 
-        let task = sqlx::query_as(sql)
-            .bind(story_id)
-            .bind(name)
-            .fetch_one(self.db_ref())
+        let mut result_set = sqlx::query(sql).bind(story_ids).fetch(self.read_ref());
+        let mut result = Vec::new();
+
+        while let Some(row) = result_set.try_next().await? {
+            let task = Task::from_row(&row)?;
+            result.push(task);
+        }
+
+        Ok(result)
+    }
+
+    /// Select incomplete tasks for an owner's stories due in a time range, soonest first. Tasks
+    /// without a due date are only included when neither bound is given, and come last.
+    #[instrument(skip(self))]
+    pub async fn fetch_for_owner(
+        &self,
+        owner: &str,
+        due_from: Option<DateTime<Utc>>,
+        due_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Task>> {
+        tracing::debug!("select_tasks: owner: {}", owner);
+
+        let sql = r#"
+            SELECT t.id, t.story_id, t.name, t.status, t.due_at
+            FROM tasks t
+            JOIN stories s ON s.id = t.story_id
+            WHERE s.owner = $1 AND s.deleted_at IS NULL AND t.deleted_at IS NULL
+            AND t.status = 'incomplete'
+            AND ($2::timestamptz IS NULL OR t.due_at >= $2)
+            AND ($3::timestamptz IS NULL OR t.due_at < $3)
+            ORDER BY t.due_at ASC NULLS LAST, t.created_at ASC
+        "#;
+
+        let tasks = sqlx::query_as(sql)
+            .bind(owner)
+            .bind(due_from)
+            .bind(due_before)
+            .fetch_all(self.read_ref())
             .await?;
 
-        Ok(task)
+        Ok(tasks)
     }
 
-    /// Update task name and status.
-    pub async fn update(&self, id: Uuid, name: String, status: Status) -> Result<Task> {
-        log::debug!("update_task: {}, {}, {}", id, name, status);
+    /// Insert a new task
+    #[instrument(skip(self))]
+    pub async fn create(
+        &self,
+        story_id: Uuid,
+        name: String,
+        due_at: Option<DateTime<Utc>>,
+    ) -> Result<Task> {
+        tracing::debug!("insert_task: {}, {}, {:?}", story_id, name, due_at);
 
         let sql = r#"
+            INSERT INTO tasks (story_id, name, due_at)
+            VALUES ($1, $2, $3)
+            RETURNING id, story_id, name, status, due_at
+        "#;
+
+        retry_serialization(|| {
+            sqlx::query_as(sql)
+                .bind(story_id)
+                .bind(name.clone())
+                .bind(due_at)
+                .fetch_one(self.db_ref())
+                .map_err(Error::from)
+        })
+        .await
+    }
+
+    /// Update task name, status and due date. Changing the due date means the owner is reminded
+    /// again, and completing a recurring task creates its next occurrence in the same transaction.
+    #[instrument(skip(self))]
+    pub async fn update(
+        &self,
+        id: Uuid,
+        name: String,
+        status: Status,
+        due_at: Option<DateTime<Utc>>,
+    ) -> Result<Task> {
+        tracing::debug!("update_task: {}, {}, {}, {:?}", id, name, status, due_at);
+        retry_serialization(|| self.try_update(id, name.clone(), status, due_at)).await
+    }
+
+    /// Update a task in a transaction, holding its row so only one completion recurs.
+    async fn try_update(
+        &self,
+        id: Uuid,
+        name: String,
+        status: Status,
+        due_at: Option<DateTime<Utc>>,
+    ) -> Result<Task> {
+        let mut transaction = self.db.begin().await?;
+
+        let select_sql = r#"
+            SELECT status = 'complete'
+            FROM tasks
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+        "#;
+
+        let Some(was_complete) = sqlx::query_scalar::<_, bool>(select_sql)
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?
+        else {
+            return Err(Error::NotFound {
+                message: format!("task not found: {}", id),
+            });
+        };
+
+        let update_sql = r#"
             UPDATE tasks
-            SET name = $1, status = $2, updated_at = now()
-            WHERE id = $3 AND deleted_at IS NULL
-            RETURNING id, story_id, name, status
+            SET name = $1, status = $2, due_at = $3, updated_at = now(),
+                reminded_at = CASE WHEN due_at IS DISTINCT FROM $3 THEN NULL ELSE reminded_at END,
+                reminder_claimed_at = CASE
+                    WHEN due_at IS DISTINCT FROM $3 THEN NULL ELSE reminder_claimed_at
+                END
+            WHERE id = $4
+            RETURNING id, story_id, name, status, due_at
         "#;
 
-        let task = sqlx::query_as(sql)
+        let task: Task = sqlx::query_as(update_sql)
             .bind(name)
             .bind(status.to_string())
+            .bind(due_at)
             .bind(id)
-            .fetch_one(self.db_ref())
+            .fetch_one(&mut *transaction)
             .await?;
 
+        // Completing a recurring task creates its next occurrence
+        if !was_complete && task.status == Status::Complete {
+            recurrence::occur(&mut transaction, id, Utc::now(), false).await?;
+        }
+
+        transaction.commit().await?;
+
         Ok(task)
     }
 
     /// Delete a task by setting the deleted_at timestamp.
+    #[instrument(skip(self))]
     pub async fn delete(&self, id: Uuid) -> Result<u64> {
-        log::debug!("delete_task: {}", id);
+        tracing::debug!("delete_task: {}", id);
 
         let sql = r#"
             UPDATE tasks SET deleted_at = now()
@@ -147,7 +316,13 @@ impl TaskRepo {
             AND deleted_at IS NULL
         "#;
 
-        let result = sqlx::query(sql).bind(id).execute(self.db_ref()).await?;
+        let result = retry_serialization(|| {
+            sqlx::query(sql)
+                .bind(id)
+                .execute(self.db_ref())
+                .map_err(Error::from)
+        })
+        .await?;
 
         Ok(result.rows_affected())
     }
@@ -160,6 +335,7 @@ mod tests {
         domain::Status,
         repo::{tests, StoryRepo},
     };
+    use chrono::Duration;
     use std::sync::Arc;
 
     use testcontainers::{clients::Cli, RunnableImage};
@@ -189,29 +365,47 @@ mod tests {
 
         // Create task, ensuring complete flag is false
         let task_name = "Suttree".to_string();
+        let due_at = "2026-10-19T09:30:00Z".parse().unwrap();
         let task = task_repo
-            .create(story_id.clone(), task_name.clone())
+            .create(story_id, task_name.clone(), Some(due_at))
             .await
             .unwrap();
         assert_eq!(task.status, Status::Incomplete);
 
+        // Query due and overdue tasks for the owner
+        let now = due_at - Duration::hours(1);
+        let due = task_repo
+            .fetch_for_owner(&owner, Some(now), Some(now + Duration::days(1)))
+            .await
+            .unwrap();
+        assert_eq!(due, vec![task.clone()]);
+        let overdue = task_repo
+            .fetch_for_owner(&owner, None, Some(now))
+            .await
+            .unwrap();
+        assert!(overdue.is_empty());
+
         // Complete task
         let task = task_repo
-            .update(task.id, task.name, Status::Complete)
+            .update(task.id, task.name, Status::Complete, task.due_at)
             .await
             .unwrap();
         assert_eq!(task.status, Status::Complete);
+        let open = task_repo.fetch_for_owner(&owner, None, None).await.unwrap();
+        assert!(open.is_empty());
 
         // Query tasks for story.
-        let tasks = task_repo.fetch_all(story_id.clone()).await.unwrap();
+        let tasks = task_repo.fetch_all(story_id).await.unwrap();
         assert_eq!(tasks.len(), 1);
+        let streamed: Vec<Task> = task_repo.stream_all(story_id).try_collect().await.unwrap();
+        assert_eq!(streamed, tasks);
 
         // Delete the task
         let updated_rows = task_repo.delete(task.id).await.unwrap();
         assert_eq!(updated_rows, 1);
 
         // Assert task was deleted
-        let tasks = task_repo.fetch_all(story_id.clone()).await.unwrap();
+        let tasks = task_repo.fetch_all(story_id).await.unwrap();
         assert!(tasks.is_empty());
 
         // Cleanup
@@ -0,0 +1,19 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use std::time::Instant;
+
+/// Middleware that logs one line per request, with its status and latency.
+/// Runs inside the request span, which carries the request id, method and route.
+pub async fn access_log(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    tracing::info!(
+        target: "gsd::access",
+        status = response.status().as_u16(),
+        latency_ms,
+        "request completed"
+    );
+
+    response
+}
@@ -0,0 +1,81 @@
+use crate::config::{Config, LogFormat};
+use std::{
+    error::Error,
+    io::{self, IsTerminal},
+};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+// Access logs
+mod access;
+
+// OpenTelemetry export
+#[cfg(feature = "otlp")]
+mod otlp;
+
+// Request ids and spans
+mod request;
+
+pub use access::access_log;
+pub use request::{grpc_span, request_id, trace_requests, REQUEST_ID_HEADER};
+
+/// Flushes exported spans when dropped.
+#[must_use]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("failed to flush spans: {}", err);
+            }
+        }
+    }
+}
+
+/// Install the global tracing subscriber, filtered by `RUST_LOG`.
+pub fn init(config: &Config) -> Result<TelemetryGuard, Box<dyn Error>> {
+    let registry = tracing_subscriber::registry()
+        .with(format_layer(config.log_format))
+        .with(EnvFilter::from_default_env());
+
+    #[cfg(feature = "otlp")]
+    {
+        let provider = match &config.otlp_endpoint {
+            Some(endpoint) => Some(otlp::tracer_provider(endpoint, &config.service_name)?),
+            None => None,
+        };
+        registry
+            .with(provider.as_ref().map(otlp::layer))
+            .try_init()?;
+        Ok(TelemetryGuard { provider })
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        registry.try_init()?;
+        if config.otlp_endpoint.is_some() {
+            tracing::warn!("OTEL_EXPORTER_OTLP_ENDPOINT is set, but the otlp feature is disabled");
+        }
+        Ok(TelemetryGuard {})
+    }
+}
+
+/// Format logs as text for people, or as one JSON object per line for log pipelines.
+/// JSON lines carry the current span and its parents, such as the request span with its id and route.
+fn format_layer(format: LogFormat) -> Box<dyn Layer<Registry> + Send + Sync> {
+    match format {
+        LogFormat::Text => fmt::layer().with_ansi(io::stdout().is_terminal()).boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
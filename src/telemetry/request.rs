@@ -0,0 +1,191 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+
+/// Header used to pass request ids to and from clients
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// W3C trace context header
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Longest accepted client supplied request id
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The id of the request being handled, if any.
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware that runs each request in a span carrying its request id,
+/// and echoes the id back in the response headers.
+pub async fn trace_requests(request: Request, next: Next) -> Response {
+    let id = incoming_request_id(request.headers()).unwrap_or_else(new_request_id);
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str().to_owned(),
+        None => request.uri().path().to_owned(),
+    };
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        route = %route,
+    );
+    set_parent(&span, request.headers());
+
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Create a span with a request id for a gRPC call.
+pub fn grpc_span<B>(request: &axum::http::Request<B>) -> Span {
+    let id = incoming_request_id(request.headers()).unwrap_or_else(new_request_id);
+    let span = tracing::info_span!(
+        "grpc",
+        request_id = %id,
+        route = %request.uri().path(),
+    );
+    set_parent(&span, request.headers());
+    span
+}
+
+/// Generate an id for requests that don't supply one.
+fn new_request_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Use a valid client supplied request id, falling back to the trace id from `traceparent`.
+fn incoming_request_id(headers: &HeaderMap) -> Option<String> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(id) = header(REQUEST_ID_HEADER).filter(|id| is_valid_request_id(id)) {
+        return Some(id.to_owned());
+    }
+    header(TRACEPARENT_HEADER)
+        .and_then(trace_id)
+        .map(str::to_owned)
+}
+
+/// Request ids are short, and limited to characters that are safe to log and echo.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Get the trace id from a `version-traceid-parentid-flags` traceparent header.
+fn trace_id(traceparent: &str) -> Option<&str> {
+    let mut parts = traceparent.trim().split('-');
+    let _version = parts.next()?;
+    let trace_id = parts.next()?;
+    let valid = trace_id.len() == 32
+        && trace_id.chars().all(|c| c.is_ascii_hexdigit())
+        && trace_id.chars().any(|c| c != '0');
+    valid.then_some(trace_id)
+}
+
+/// Link a span to the caller's trace when exporting.
+#[cfg(feature = "otlp")]
+fn set_parent(span: &Span, headers: &HeaderMap) {
+    super::otlp::set_parent(span, headers);
+}
+
+/// Trace context is only propagated when exporting.
+#[cfg(not(feature = "otlp"))]
+fn set_parent(_span: &Span, _headers: &HeaderMap) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/stories/:id",
+                get(|| async {
+                    Err::<(), _>(Error::NotFound {
+                        message: "story not found".into(),
+                    })
+                }),
+            )
+            .layer(middleware::from_fn(trace_requests))
+    }
+
+    async fn send(request: Request) -> (Option<String>, serde_json::Value) {
+        let response = router().oneshot(request).await.unwrap();
+        let header = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .map(|value| value.to_str().unwrap().to_owned());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn echo_request_ids() {
+        // Client supplied ids are echoed in headers and error bodies
+        let request = Request::get("/stories/1")
+            .header(REQUEST_ID_HEADER, "abc-123")
+            .body(Body::empty())
+            .unwrap();
+        let (header, body) = send(request).await;
+        assert_eq!(header.as_deref(), Some("abc-123"));
+        assert_eq!(body["request_id"], "abc-123");
+
+        // The trace id is used when only a traceparent is sent
+        let request = Request::get("/stories/1")
+            .header(
+                TRACEPARENT_HEADER,
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(Body::empty())
+            .unwrap();
+        let (header, _) = send(request).await;
+        assert_eq!(header.as_deref(), Some("4bf92f3577b34da6a3ce929d0e0e4736"));
+
+        // Otherwise an id is generated, ignoring invalid ones
+        let request = Request::get("/stories/1")
+            .header(REQUEST_ID_HEADER, "not valid!")
+            .body(Body::empty())
+            .unwrap();
+        let (header, body) = send(request).await;
+        let id = header.unwrap();
+        assert_eq!(id.len(), 32);
+        assert_eq!(body["request_id"], id.as_str());
+    }
+
+    #[test]
+    fn parse_traceparent() {
+        assert_eq!(
+            trace_id("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+        assert_eq!(
+            trace_id("00-00000000000000000000000000000000-00f067aa0ba902b7-01"),
+            None
+        );
+        assert_eq!(trace_id("garbage"), None);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::config::Config;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+use tokio_rustls::rustls::{
+    self,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::{VerifierBuilderError, WebPkiClientVerifier},
+    RootCertStore, ServerConfig,
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+// TLS connection handling
+mod serve;
+
+pub use serve::serve_tls;
+
+/// ALPN protocols, preferring HTTP/2
+const ALPN_PROTOCOLS: &[&[u8]] = &[b"h2", b"http/1.1"];
+
+/// Errors loading TLS certificates and keys.
+#[derive(thiserror::Error, Debug)]
+pub enum TlsError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("no private key found in {0}")]
+    MissingKey(PathBuf),
+    #[error("no certificates found in {0}")]
+    MissingCerts(PathBuf),
+    #[error("invalid client CA: {0}")]
+    ClientCa(#[from] VerifierBuilderError),
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+}
+
+/// The authenticated identity of a client, from the subject of its TLS certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal(pub String);
+
+impl Principal {
+    /// Map a certificate subject to a principal, using the common name when there is one.
+    pub fn from_certificate(cert: &CertificateDer) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(cert.as_ref()).ok()?;
+        let subject = cert.subject();
+        let name = match subject.iter_common_name().next() {
+            Some(cn) => cn.as_str().ok()?.to_owned(),
+            None => subject.to_string(),
+        };
+        Some(Self(name))
+    }
+}
+
+/// Paths to PEM encoded TLS files.
+#[derive(Debug, Clone)]
+struct TlsPaths {
+    cert: PathBuf,
+    key: PathBuf,
+    client_ca: Option<PathBuf>,
+    client_auth_required: bool,
+}
+
+/// TLS settings that are reloaded when the certificate files change.
+pub struct Tls {
+    paths: TlsPaths,
+    current: RwLock<(Arc<ServerConfig>, Vec<Option<SystemTime>>)>,
+}
+
+impl Tls {
+    /// Load TLS settings from config, or `None` when TLS is not configured.
+    pub fn load(config: &Config) -> Result<Option<Self>, TlsError> {
+        let (Some(cert), Some(key)) = (&config.tls_cert_path, &config.tls_key_path) else {
+            return Ok(None);
+        };
+        let paths = TlsPaths {
+            cert: cert.clone(),
+            key: key.clone(),
+            client_ca: config.tls_client_ca_path.clone(),
+            client_auth_required: config.tls_client_auth_required,
+        };
+        let server_config = server_config(&paths)?;
+        let modified = modified_times(&paths);
+        Ok(Some(Self {
+            paths,
+            current: RwLock::new((Arc::new(server_config), modified)),
+        }))
+    }
+
+    /// The server config to use for new connections.
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&current.0)
+    }
+
+    /// Reload certificates when any of the files changed. Returns whether a reload happened.
+    /// On errors the current certificates stay in use.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let modified = modified_times(&self.paths);
+        {
+            let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+            if current.1 == modified {
+                return Ok(false);
+            }
+        }
+
+        let server_config = server_config(&self.paths)?;
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        *current = (Arc::new(server_config), modified);
+        Ok(true)
+    }
+}
+
+/// Build a rustls server config, verifying client certificates when a client CA is set.
+fn server_config(paths: &TlsPaths) -> Result<ServerConfig, TlsError> {
+    let provider = Arc::new(ring::default_provider());
+    let certs = read_certs(&paths.cert)?;
+    let key = read_key(&paths.key)?;
+
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()?;
+    let builder = match &paths.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(path)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if paths.client_auth_required {
+                verifier.build()?
+            } else {
+                verifier.allow_unauthenticated().build()?
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key)?;
+    server_config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+    Ok(server_config)
+}
+
+/// Read a PEM file.
+fn read(path: &Path) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|source| TlsError::Read {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Read all certificates from a PEM file.
+pub(crate) fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let pem = read(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|source| TlsError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+    if certs.is_empty() {
+        return Err(TlsError::MissingCerts(path.to_owned()));
+    }
+    Ok(certs)
+}
+
+/// Read the first private key from a PEM file.
+pub(crate) fn read_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError> {
+    let pem = read(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|source| TlsError::Read {
+            path: path.to_owned(),
+            source,
+        })?
+        .ok_or_else(|| TlsError::MissingKey(path.to_owned()))
+}
+
+/// Modification times of the TLS files, used to detect changes.
+fn modified_times(paths: &TlsPaths) -> Vec<Option<SystemTime>> {
+    [
+        Some(&paths.cert),
+        Some(&paths.key),
+        paths.client_ca.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::api_ctx;
+    use axum::{routing::get, Extension, Router};
+    use rcgen::{
+        BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    };
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::{rustls::ClientConfig, TlsConnector};
+    use tokio_util::sync::CancellationToken;
+
+    /// A test CA issuing server and client certificates.
+    struct TestCerts {
+        ca: String,
+        server: (String, String),
+        client: (String, String),
+    }
+
+    fn test_certs() -> TestCerts {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "gsd test ca");
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let issue = |names: Vec<String>, cn: &str, usage: ExtendedKeyUsagePurpose| {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(names).unwrap();
+            params.distinguished_name.push(DnType::CommonName, cn);
+            params.extended_key_usages = vec![usage];
+            let cert = params.signed_by(&key, &ca, &ca_key).unwrap();
+            (cert.pem(), key.serialize_pem())
+        };
+
+        TestCerts {
+            ca: ca.pem(),
+            server: issue(
+                vec!["localhost".into()],
+                "localhost",
+                ExtendedKeyUsagePurpose::ServerAuth,
+            ),
+            client: issue(vec![], "gsd-cli", ExtendedKeyUsagePurpose::ClientAuth),
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_with_mutual_tls() {
+        let certs = test_certs();
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, pem: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, pem).unwrap();
+            path
+        };
+
+        let mut config = api_ctx().config.as_ref().clone();
+        config.tls_cert_path = Some(write("server.pem", &certs.server.0));
+        config.tls_key_path = Some(write("server.key", &certs.server.1));
+        config.tls_client_ca_path = Some(write("ca.pem", &certs.ca));
+        let tls = Arc::new(Tls::load(&config).unwrap().unwrap());
+
+        // Serve a route that reports the client principal
+        let router = Router::new().route(
+            "/whoami",
+            get(|principal: Option<Extension<Principal>>| async move {
+                principal
+                    .map(|Extension(Principal(p))| p)
+                    .unwrap_or_default()
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve_tls(
+            listener,
+            router,
+            Arc::clone(&tls),
+            shutdown.clone(),
+        ));
+
+        // Client certificate subjects map to principals
+        let mut identity = certs.client.0.clone();
+        identity.push_str(&certs.client.1);
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(reqwest::Certificate::from_pem(certs.ca.as_bytes()).unwrap())
+            .identity(reqwest::Identity::from_pem(identity.as_bytes()).unwrap())
+            .build()
+            .unwrap();
+        let url = format!("https://localhost:{}/whoami", port);
+        let principal = client.get(&url).send().await.unwrap().text().await.unwrap();
+        assert_eq!(principal, "gsd-cli");
+
+        // HTTP/2 is negotiated with ALPN, and client certificates are optional
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(config.tls_client_ca_path.as_ref().unwrap()).unwrap() {
+            roots.add(cert).unwrap();
+        }
+        let mut client_config =
+            ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        client_config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let stream = TlsConnector::from(Arc::new(client_config))
+            .connect("localhost".try_into().unwrap(), stream)
+            .await
+            .unwrap();
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+        drop(stream);
+
+        // Certificates are reloaded only when files change
+        assert!(!tls.reload_if_changed().unwrap());
+        let renewed = test_certs();
+        write("server.pem", &renewed.server.0);
+        write("server.key", &renewed.server.1);
+        write("ca.pem", &renewed.ca);
+        assert!(tls.reload_if_changed().unwrap());
+
+        shutdown.cancel();
+        server.await.unwrap().unwrap();
+    }
+}
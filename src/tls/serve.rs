@@ -0,0 +1,83 @@
+use crate::tls::{Principal, Tls};
+use axum::{body::Body, extract::ConnectInfo, Router};
+use hyper::{body::Incoming, Request};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+};
+use std::{io, sync::Arc};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tower::ServiceExt;
+
+/// Serve a router over TLS, negotiating HTTP/2 or HTTP/1.1 with ALPN.
+/// Stops accepting connections when the shutdown token is cancelled, then drains open connections.
+pub async fn serve_tls(
+    listener: TcpListener,
+    router: Router,
+    tls: Arc<Tls>,
+    shutdown: CancellationToken,
+) -> io::Result<()> {
+    let connections = TaskTracker::new();
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!("failed to accept connection: {}", err);
+                    continue;
+                }
+            },
+        };
+
+        let acceptor = TlsAcceptor::from(tls.server_config());
+        let router = router.clone();
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::debug!("TLS handshake with {} failed: {}", addr, err);
+                    return;
+                }
+            };
+            let principal = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(Principal::from_certificate);
+
+            let service = hyper::service::service_fn(move |request: Request<Incoming>| {
+                let mut request = request.map(Body::new);
+                request.extensions_mut().insert(ConnectInfo(addr));
+                if let Some(principal) = &principal {
+                    request.extensions_mut().insert(principal.clone());
+                }
+                router.clone().oneshot(request)
+            });
+
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = shutdown.cancelled() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(err) = result {
+                tracing::debug!("connection with {} closed: {}", addr, err);
+            }
+        });
+    }
+
+    connections.close();
+    connections.wait().await;
+    Ok(())
+}